@@ -1,7 +1,493 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+pub type Registers = [usize; 4];
+
+pub struct Manual {
+    samples: Vec<Sample>,
+    program: Vec<Instruction>,
+}
+
+impl Manual {
+    pub fn samples_behaving_like_at_least(&self, n: usize) -> usize {
+        self.samples
+            .iter()
+            .filter(|sample| sample.behaves_like().count() >= n)
+            .count()
+    }
+
+    pub fn resolver(&self) -> OpcodeResolver {
+        let mut resolver = OpcodeResolver::default();
+        for sample in self.samples() {
+            resolver.observe(sample);
+        }
+        for instruction in self.program() {
+            resolver.require(instruction.opcode);
+        }
+        resolver
+    }
+
+    /// Runs the test program from empty registers and returns register 0.
+    pub fn run_program(&self) -> Result<usize, ProgramError> {
+        let opcodes = self.resolver().resolve()?;
+        let registers =
+            self.program()
+                .enumerate()
+                .try_fold([0; 4], |registers, (index, instruction)| {
+                    opcodes[&instruction.opcode]
+                        .execute(instruction, &registers)
+                        .ok_or(ProgramError::Faulted { index })
+                })?;
+        Ok(registers[0])
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &Sample> {
+        self.samples.iter()
+    }
+
+    pub fn program(&self) -> impl Iterator<Item = &Instruction> {
+        self.program.iter()
+    }
+}
+
+impl FromStr for Manual {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.replace("\r\n", "\n");
+        let (samples, program) = match s.find("\n\n\n") {
+            Some(split) => (&s[..split], &s[split..]),
+            None => (&s[..], ""),
+        };
+        Ok(Manual {
+            samples: samples
+                .split("\n\n")
+                .filter(|block| !block.trim().is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+            program: program
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Sample {
+    before: Registers,
+    instruction: Instruction,
+    after: Registers,
+}
+
+impl Sample {
+    pub fn behaves_like<'a>(&'a self) -> impl Iterator<Item = Opcode> + 'a {
+        Opcode::ALL.iter().cloned().filter(move |opcode| {
+            opcode.execute(&self.instruction, &self.before) == Some(self.after)
+        })
+    }
+}
+
+impl FromStr for Sample {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        let before = parse_registers(lines.next().ok_or(())?, "Before:")?;
+        let instruction = lines.next().ok_or(())?.parse()?;
+        let after = parse_registers(lines.next().ok_or(())?, "After:")?;
+        if lines.next().is_some() {
+            return Err(());
+        }
+        Ok(Sample {
+            before,
+            instruction,
+            after,
+        })
+    }
+}
+
+fn parse_registers(line: &str, label: &str) -> Result<Registers, ()> {
+    let list = line
+        .trim_start_matches(label)
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let mut registers = [0; 4];
+    let mut values = list.split(',').map(|v| v.trim().parse().map_err(|_| ()));
+    for register in registers.iter_mut() {
+        *register = values.next().ok_or(())??;
+    }
+    if values.next().is_some() {
+        return Err(());
+    }
+    Ok(registers)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Instruction {
+    opcode: usize,
+    a: usize,
+    b: usize,
+    c: usize,
+}
+
+impl FromStr for Instruction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = s.split_whitespace().map(|v| v.parse().map_err(|_| ()));
+        let mut next = || values.next().ok_or(())?;
+        let instruction = Instruction {
+            opcode: next()?,
+            a: next()?,
+            b: next()?,
+            c: next()?,
+        };
+        if next().is_ok() {
+            return Err(());
+        }
+        Ok(instruction)
+    }
+}
+
+/// Works out which opcode each opcode number refers to.
+#[derive(Debug, Default)]
+pub struct OpcodeResolver {
+    candidates: BTreeMap<usize, BTreeSet<Opcode>>,
+}
+
+impl OpcodeResolver {
+    /// Narrows the sample's opcode number down to the opcodes it behaves like.
+    pub fn observe(&mut self, sample: &Sample) {
+        let behaves_like = sample.behaves_like().collect::<BTreeSet<_>>();
+        self.require(sample.instruction.opcode)
+            .retain(|opcode| behaves_like.contains(opcode));
+    }
+
+    /// Makes sure the opcode number gets resolved, even if no sample uses it.
+    pub fn require(&mut self, opcode_number: usize) -> &mut BTreeSet<Opcode> {
+        self.candidates
+            .entry(opcode_number)
+            .or_insert_with(|| Opcode::ALL.iter().cloned().collect())
+    }
+
+    pub fn resolve(&self) -> Result<BTreeMap<usize, Opcode>, ResolutionError> {
+        let mut candidates = self.candidates.clone();
+        let mut resolved = BTreeMap::new();
+        // Only when every opcode has a number must each free opcode belong
+        // to one of the remaining numbers.
+        let every_opcode_numbered = self.candidates.len() == Opcode::ALL.len();
+        loop {
+            let single_candidate = candidates
+                .iter()
+                .find(|(_, opcodes)| opcodes.len() == 1)
+                .map(|(&number, opcodes)| (number, *opcodes.iter().next().unwrap()));
+            let single_number = || {
+                Opcode::ALL
+                    .iter()
+                    .filter(|_| every_opcode_numbered)
+                    .find_map(|&opcode| {
+                        let mut numbers = candidates
+                            .iter()
+                            .filter(|(_, opcodes)| opcodes.contains(&opcode));
+                        match (numbers.next(), numbers.next()) {
+                            (Some((&number, _)), None) => Some((number, opcode)),
+                            _ => None,
+                        }
+                    })
+            };
+            match single_candidate.or_else(single_number) {
+                Some((number, opcode)) => {
+                    candidates.remove(&number);
+                    for opcodes in candidates.values_mut() {
+                        opcodes.remove(&opcode);
+                    }
+                    resolved.insert(number, opcode);
+                }
+                None => break,
+            }
+        }
+
+        let contradictory = candidates
+            .iter()
+            .filter(|(_, opcodes)| opcodes.is_empty())
+            .map(|(&number, _)| number)
+            .collect::<Vec<_>>();
+        if !contradictory.is_empty() {
+            return Err(ResolutionError::Contradictory(contradictory));
+        }
+        if !candidates.is_empty() {
+            return Err(ResolutionError::Ambiguous(candidates));
+        }
+        Ok(resolved)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ResolutionError {
+    /// These opcode numbers don't behave like any opcode that is still free.
+    Contradictory(Vec<usize>),
+    /// These opcode numbers could still be more than one opcode.
+    Ambiguous(BTreeMap<usize, BTreeSet<Opcode>>),
+}
+
+impl fmt::Display for ResolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResolutionError::Contradictory(numbers) => write!(
+                f,
+                "opcode numbers {:?} don't behave like any free opcode",
+                numbers
+            ),
+            ResolutionError::Ambiguous(candidates) => {
+                write!(f, "opcode numbers are ambiguous:")?;
+                for (number, opcodes) in candidates {
+                    write!(f, " {} could be {:?};", number, opcodes)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ResolutionError {}
+
+#[derive(Debug, PartialEq)]
+pub enum ProgramError {
+    Unresolved(ResolutionError),
+    /// The instruction at this index of the program refers to a missing
+    /// register or overflows.
+    Faulted {
+        index: usize,
+    },
+}
+
+impl From<ResolutionError> for ProgramError {
+    fn from(error: ResolutionError) -> Self {
+        ProgramError::Unresolved(error)
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramError::Unresolved(error) => error.fmt(f),
+            ProgramError::Faulted { index } => write!(
+                f,
+                "instruction {} refers to a missing register or overflows",
+                index
+            ),
+        }
+    }
+}
+
+impl Error for ProgramError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProgramError::Unresolved(error) => Some(error),
+            ProgramError::Faulted { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub enum Opcode {
+    Addr,
+    Addi,
+    Mulr,
+    Muli,
+    Banr,
+    Bani,
+    Borr,
+    Bori,
+    Setr,
+    Seti,
+    Gtir,
+    Gtri,
+    Gtrr,
+    Eqir,
+    Eqri,
+    Eqrr,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 16] = [
+        Opcode::Addr,
+        Opcode::Addi,
+        Opcode::Mulr,
+        Opcode::Muli,
+        Opcode::Banr,
+        Opcode::Bani,
+        Opcode::Borr,
+        Opcode::Bori,
+        Opcode::Setr,
+        Opcode::Seti,
+        Opcode::Gtir,
+        Opcode::Gtri,
+        Opcode::Gtrr,
+        Opcode::Eqir,
+        Opcode::Eqri,
+        Opcode::Eqrr,
+    ];
+
+    /// Runs the instruction as if it were this opcode.
+    ///
+    /// Returns `None` if the instruction refers to a register that doesn't
+    /// exist, which means it can't be this opcode.
+    pub fn execute(self, instruction: &Instruction, registers: &Registers) -> Option<Registers> {
+        use crate::Opcode::*;
+        let Instruction { a, b, c, .. } = *instruction;
+        let reg = |r: usize| registers.get(r).cloned();
+        let value = match self {
+            Addr => reg(a)?.checked_add(reg(b)?)?,
+            Addi => reg(a)?.checked_add(b)?,
+            Mulr => reg(a)?.checked_mul(reg(b)?)?,
+            Muli => reg(a)?.checked_mul(b)?,
+            Banr => reg(a)? & reg(b)?,
+            Bani => reg(a)? & b,
+            Borr => reg(a)? | reg(b)?,
+            Bori => reg(a)? | b,
+            Setr => reg(a)?,
+            Seti => a,
+            Gtir => (a > reg(b)?) as usize,
+            Gtri => (reg(a)? > b) as usize,
+            Gtrr => (reg(a)? > reg(b)?) as usize,
+            Eqir => (a == reg(b)?) as usize,
+            Eqri => (reg(a)? == b) as usize,
+            Eqrr => (reg(a)? == reg(b)?) as usize,
+        };
+        let mut output = *registers;
+        *output.get_mut(c)? = value;
+        Some(output)
+    }
+}
+
 #[cfg(test)]
-mod tests {
+mod worked_example {
+    use crate::Instruction;
+    use crate::Manual;
+    use crate::Opcode;
+    use crate::OpcodeResolver;
+    use crate::ResolutionError;
+    use crate::Sample;
+
+    fn manual() -> Manual {
+        include_str!("../example.txt").parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            manual().samples().collect::<Vec<_>>(),
+            vec![&Sample {
+                before: [3, 2, 1, 1],
+                instruction: Instruction {
+                    opcode: 9,
+                    a: 2,
+                    b: 1,
+                    c: 2
+                },
+                after: [3, 2, 2, 1],
+            }]
+        );
+    }
+
+    #[test]
+    fn behaves_like() {
+        assert_eq!(
+            manual()
+                .samples()
+                .flat_map(Sample::behaves_like)
+                .collect::<Vec<_>>(),
+            vec![Opcode::Addi, Opcode::Mulr, Opcode::Seti]
+        );
+    }
+
+    #[test]
+    fn samples_behaving_like_at_least() {
+        assert_eq!(manual().samples_behaving_like_at_least(3), 1);
+    }
+
+    #[test]
+    fn ambiguous() {
+        assert_eq!(
+            manual().resolver().resolve(),
+            Err(ResolutionError::Ambiguous(
+                vec![(9, vec![Opcode::Addi, Opcode::Mulr, Opcode::Seti])]
+                    .into_iter()
+                    .map(|(number, opcodes)| (number, opcodes.into_iter().collect()))
+                    .collect()
+            ))
+        );
+    }
+
+    #[test]
+    fn contradictory() {
+        let sample = |opcode, after| Sample {
+            before: [3, 2, 1, 1],
+            instruction: Instruction {
+                opcode,
+                a: 2,
+                b: 1,
+                c: 2,
+            },
+            after,
+        };
+        let mut resolver = OpcodeResolver::default();
+        resolver.observe(&sample(9, [3, 2, 2, 1]));
+        resolver.observe(&sample(9, [3, 2, 9, 1]));
+        assert_eq!(
+            resolver.resolve(),
+            Err(ResolutionError::Contradictory(vec![9]))
+        );
+    }
+}
+
+#[cfg(test)]
+mod puzzle {
+    use crate::Manual;
+    use crate::ProgramError;
+
+    fn manual() -> Manual {
+        include_str!("../input.txt").parse().unwrap()
+    }
+
+    #[test]
+    fn samples_behaving_like_at_least() {
+        assert_eq!(manual().samples_behaving_like_at_least(3), 651);
+    }
+
+    #[test]
+    fn resolve() {
+        assert_eq!(manual().resolver().resolve().map(|o| o.len()), Ok(16));
+    }
+
+    #[test]
+    fn run_program() {
+        assert_eq!(manual().run_program(), Ok(706));
+    }
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn faulted() {
+        let input = format!("{}\n3 7 7 7\n", include_str!("../input.txt").trim_end());
+        let manual: Manual = input.parse().unwrap();
+        assert_eq!(
+            manual.run_program(),
+            Err(ProgramError::Faulted {
+                index: manual.program().count() - 1
+            })
+        );
+        assert_eq!(
+            manual.run_program().unwrap_err().to_string(),
+            format!(
+                "instruction {} refers to a missing register or overflows",
+                manual.program().count() - 1
+            )
+        );
     }
 }
@@ -1,8 +1,16 @@
-pub fn frequency(input: &'static str) -> i32 {
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::BufRead;
+
+pub fn frequency(input: &str) -> i32 {
     numbers_iterator(input).sum()
 }
 
-pub fn repeated_frequency(input: &'static str) -> i32 {
+pub fn repeated_frequency(input: &str) -> i32 {
     let mut seen = std::collections::HashSet::new();
     let mut cumulative_sum = 0;
     loop {
@@ -15,7 +23,140 @@ pub fn repeated_frequency(input: &'static str) -> i32 {
     }
 }
 
-fn numbers_iterator(input: &'static str) -> impl Iterator<Item = i32> + Clone {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Repeat {
+    pub frequency: i64,
+    /// How many deltas had been applied when the frequency was reached the
+    /// second time.
+    pub step: u64,
+}
+
+/// Like `repeated_frequency`, but returns `None` instead of looping forever
+/// when no frequency is ever reached twice.
+///
+/// Rather than visiting every frequency, it uses the drift over one pass
+/// of the input. The frequency after `i` deltas of the `k`th pass is
+/// `sums[i] + k * drift`, so only partial sums that are congruent modulo
+/// the drift can meet, and the lower one catches up with the higher one
+/// after `(higher - lower) / drift` passes.
+pub fn first_repeat(input: &str) -> Option<Repeat> {
+    let mut sums = Vec::new();
+    let mut sum = 0i64;
+    for delta in numbers_iterator(input) {
+        sums.push(sum);
+        sum += i64::from(delta);
+    }
+    let drift = sum;
+    let len = sums.len() as u64;
+
+    let mut seen = HashSet::new();
+    for (step, &sum) in sums.iter().enumerate() {
+        if !seen.insert(sum) {
+            return Some(Repeat {
+                frequency: sum,
+                step: step as u64,
+            });
+        }
+    }
+    if drift == 0 {
+        return sums.first().map(|&frequency| Repeat {
+            frequency,
+            step: len,
+        });
+    }
+
+    // Flip a negative drift, so sums only ever catch up by increasing.
+    let direction = drift.signum();
+    let drift = drift.abs();
+    let mut residues: HashMap<i64, Vec<(i64, u64)>> = HashMap::new();
+    for (i, &sum) in sums.iter().enumerate() {
+        let sum = sum * direction;
+        residues
+            .entry(sum.rem_euclid(drift))
+            .or_default()
+            .push((sum, i as u64));
+    }
+    residues
+        .values_mut()
+        .flat_map(|group| {
+            // No two sums in the first pass are equal, so each sum is
+            // caught up by the one just below it.
+            group.sort();
+            group
+                .windows(2)
+                .map(|pair| {
+                    let ((lower, i), (higher, _)) = (pair[0], pair[1]);
+                    Repeat {
+                        frequency: higher * direction,
+                        step: (higher - lower) as u64 / drift as u64 * len + i,
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .min_by_key(|repeat| repeat.step)
+}
+
+/// How the frequency drifts over a number of passes through the input.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DriftReport {
+    pub deltas_per_cycle: usize,
+    /// The frequency before the first delta and after every delta.
+    pub series: Vec<i64>,
+    pub min: i64,
+    pub max: i64,
+    /// The first frequency reached twice, even if it's after the cycles
+    /// covered by the series.
+    pub first_repeat: Option<Repeat>,
+    /// Every frequency the series reaches more than once.
+    pub repeated: BTreeSet<i64>,
+}
+
+impl DriftReport {
+    pub fn new(input: &str, cycles: usize) -> Self {
+        let deltas = numbers_iterator(input);
+        let deltas_per_cycle = deltas.clone().count();
+        let mut series = vec![0];
+        let mut frequency = 0;
+        for delta in deltas.cycle().take(deltas_per_cycle * cycles) {
+            frequency += i64::from(delta);
+            series.push(frequency);
+        }
+
+        let mut seen = HashSet::new();
+        let repeated = series
+            .iter()
+            .cloned()
+            .filter(|&frequency| !seen.insert(frequency))
+            .collect();
+        DriftReport {
+            deltas_per_cycle,
+            min: *series.iter().min().unwrap(),
+            max: *series.iter().max().unwrap(),
+            series,
+            first_repeat: first_repeat(input),
+            repeated,
+        }
+    }
+
+    /// Which pass through the input the first repeat happens in, counting
+    /// from 0.
+    pub fn first_repeat_cycle(&self) -> Option<u64> {
+        self.first_repeat
+            .map(|repeat| repeat.step / self.deltas_per_cycle as u64)
+    }
+
+    /// The series as CSV, with columns for the step, cycle and frequency.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,cycle,frequency\n");
+        for (step, frequency) in self.series.iter().enumerate() {
+            let cycle = step.checked_div(self.deltas_per_cycle).unwrap_or(0);
+            csv.push_str(&format!("{},{},{}\n", step, cycle, frequency));
+        }
+        csv
+    }
+}
+
+fn numbers_iterator<'a>(input: &'a str) -> impl Iterator<Item = i32> + Clone + 'a {
     NumberIterator {
         chars: input.chars().peekable(),
     }
@@ -58,6 +199,99 @@ impl<'a> Iterator for NumberIterator<'a> {
     }
 }
 
+/// Sums the deltas read from the reader, stopping at the first error.
+pub fn read_frequency<R: BufRead>(reader: R) -> Result<i32, DeltaError> {
+    deltas(reader).map(|delta| delta.map(|d| d.value)).sum()
+}
+
+/// Reads frequency deltas, separated by commas or whitespace, one line at a
+/// time.
+pub fn deltas<R: BufRead>(reader: R) -> Deltas<R> {
+    Deltas {
+        lines: reader.lines(),
+        line: 0,
+        tokens: Vec::new(),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Delta {
+    /// The line the delta was on, counting from 1.
+    pub line: usize,
+    pub value: i32,
+}
+
+#[derive(Debug)]
+pub enum DeltaError {
+    Io(io::Error),
+    Malformed {
+        line: usize,
+        /// The character the token starts at, counting from 1.
+        column: usize,
+        token: String,
+    },
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeltaError::Io(error) => write!(f, "{}", error),
+            DeltaError::Malformed {
+                line,
+                column,
+                token,
+            } => write!(
+                f,
+                "{}:{}: {:?} is not a frequency delta",
+                line, column, token
+            ),
+        }
+    }
+}
+
+impl Error for DeltaError {}
+
+pub struct Deltas<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    /// The tokens left on the current line, with their columns, in reverse.
+    tokens: Vec<(usize, String)>,
+}
+
+impl<R: BufRead> Iterator for Deltas<R> {
+    type Item = Result<Delta, DeltaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.tokens.is_empty() {
+            let text = match self.lines.next()? {
+                Ok(text) => text,
+                Err(error) => return Some(Err(DeltaError::Io(error))),
+            };
+            self.line += 1;
+            let mut column = 0;
+            for part in text.split(|c: char| c == ',' || c.is_whitespace()) {
+                if !part.is_empty() {
+                    self.tokens.push((column + 1, part.to_string()));
+                }
+                column += part.chars().count() + 1;
+            }
+            self.tokens.reverse();
+        }
+        let (column, token) = self.tokens.pop()?;
+        Some(match token.parse() {
+            Ok(value) => Ok(Delta {
+                line: self.line,
+                value,
+            }),
+            Err(_) => Err(DeltaError::Malformed {
+                line: self.line,
+                column,
+                token,
+            }),
+        })
+    }
+}
+
 #[cfg(test)]
 mod frequency {
     use frequency;
@@ -84,6 +318,200 @@ mod frequency {
     fn puzzle() {
         assert_eq!(frequency(include_str!("../input.txt")), 493);
     }
+
+    #[test]
+    fn not_static() {
+        let input = String::from("+1, -2, +3, +1");
+        assert_eq!(frequency(&input), 3);
+    }
+}
+
+#[cfg(test)]
+mod first_repeat {
+    use first_repeat;
+    use repeated_frequency;
+    use Repeat;
+
+    #[test]
+    fn examples() {
+        for &input in [
+            "+1, -2, +3, +1",
+            "+1, -1",
+            "+3, +3, +4, -2, -4",
+            "-6, +3, +8, +5, -6",
+            "+7, +7, -2, -7, -4",
+            "-1, -1, +4",
+            "+1000, -999",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                first_repeat(input).map(|r| r.frequency),
+                Some(i64::from(repeated_frequency(input))),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn step() {
+        assert_eq!(
+            first_repeat("+1, -2, +3, +1"),
+            Some(Repeat {
+                frequency: 2,
+                step: 6
+            })
+        );
+        assert_eq!(
+            first_repeat("+1, -1"),
+            Some(Repeat {
+                frequency: 0,
+                step: 2
+            })
+        );
+        assert_eq!(
+            first_repeat("+1000000, -999999"),
+            Some(Repeat {
+                frequency: 1000000,
+                step: 2000000
+            })
+        );
+    }
+
+    #[test]
+    fn never_repeats() {
+        assert_eq!(first_repeat("+1, +1"), None);
+        assert_eq!(first_repeat("-1, +3"), None);
+        assert_eq!(first_repeat(""), None);
+    }
+
+    #[test]
+    fn puzzle() {
+        assert_eq!(
+            first_repeat(include_str!("../input.txt")).map(|r| r.frequency),
+            Some(413)
+        );
+    }
+}
+
+#[cfg(test)]
+mod drift_report {
+    use DriftReport;
+    use Repeat;
+
+    #[test]
+    fn worked_example() {
+        let report = DriftReport::new("+1, -2, +3, +1", 2);
+        assert_eq!(report.series, vec![0, 1, -1, 2, 3, 4, 2, 5, 6]);
+        assert_eq!((report.min, report.max), (-1, 6));
+        assert_eq!(
+            report.first_repeat,
+            Some(Repeat {
+                frequency: 2,
+                step: 6
+            })
+        );
+        assert_eq!(report.first_repeat_cycle(), Some(1));
+        assert_eq!(report.repeated.into_iter().collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn repeats_after_series() {
+        let report = DriftReport::new("+1, -2, +3, +1", 1);
+        assert_eq!(report.series, vec![0, 1, -1, 2, 3]);
+        assert!(report.repeated.is_empty());
+        assert_eq!(report.first_repeat_cycle(), Some(1));
+    }
+
+    #[test]
+    fn never_repeats() {
+        let report = DriftReport::new("+1, +1", 3);
+        assert_eq!(report.max, 6);
+        assert_eq!(report.first_repeat, None);
+        assert_eq!(report.first_repeat_cycle(), None);
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            DriftReport::new("+1, -1", 2).to_csv(),
+            "step,cycle,frequency\n0,0,0\n1,0,1\n2,1,0\n3,1,1\n4,2,0\n"
+        );
+        assert_eq!(
+            DriftReport::new("", 2).to_csv(),
+            "step,cycle,frequency\n0,0,0\n"
+        );
+    }
+
+    #[test]
+    fn puzzle() {
+        let report = DriftReport::new(include_str!("../input.txt"), 1);
+        assert_eq!(report.series.last(), Some(&493));
+        assert_eq!(report.first_repeat.map(|r| r.frequency), Some(413));
+    }
+}
+
+#[cfg(test)]
+mod deltas {
+    use deltas;
+    use read_frequency;
+    use Delta;
+    use DeltaError;
+
+    #[test]
+    fn line_numbers() {
+        assert_eq!(
+            deltas("+1, -2\n\n+3\n".as_bytes())
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+            vec![
+                Delta { line: 1, value: 1 },
+                Delta { line: 1, value: -2 },
+                Delta { line: 3, value: 3 },
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let results = deltas("+1, +x2\n  -3 4-\n".as_bytes())
+            .map(|delta| match delta {
+                Ok(delta) => Ok(delta.value),
+                Err(DeltaError::Malformed {
+                    line,
+                    column,
+                    token,
+                }) => Err((line, column, token)),
+                Err(DeltaError::Io(error)) => panic!("{}", error),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![
+                Ok(1),
+                Err((1, 5, "+x2".to_string())),
+                Ok(-3),
+                Err((2, 6, "4-".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        match deltas("+99999999999".as_bytes()).next() {
+            Some(Err(DeltaError::Malformed { column: 1, .. })) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn puzzle() {
+        assert_eq!(
+            read_frequency(&include_bytes!("../input.txt")[..]).unwrap(),
+            493
+        );
+    }
 }
 
 #[cfg(test)]
@@ -1,46 +1,390 @@
+extern crate unicode_segmentation;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
-use std::collections::HashSet;
-use std::iter::FromIterator;
-pub fn checksum(list: &str) -> u32 {
-    let (doubles, triples) = list.split_whitespace().map(id_analysis).fold(
-        (0, 0),
-        |(doubles, triples), (double, triple)| {
-            (
-                doubles + if double { 1 } else { 0 },
-                triples + if triple { 1 } else { 0 },
-            )
-        },
-    );
-    doubles * triples
+use std::fmt;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The number of IDs with a letter appearing exactly twice, times the
+/// number with one appearing exactly three times. Returns `None` if that
+/// overflows.
+pub fn checksum(list: &str) -> Option<u64> {
+    ChecksumReport::new(list, &[2, 3]).checksum()
+}
+
+/// Which of the requested multiplicities each ID has a letter repeated
+/// exactly that many times for.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ChecksumReport<'a> {
+    pub multiplicities: BTreeSet<usize>,
+    pub ids: Vec<IdBreakdown<'a>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IdBreakdown<'a> {
+    pub id: &'a str,
+    pub multiplicities: BTreeSet<usize>,
+}
+
+impl<'a> ChecksumReport<'a> {
+    pub fn new(list: &'a str, multiplicities: &[usize]) -> Self {
+        let multiplicities: BTreeSet<usize> = multiplicities.iter().cloned().collect();
+        let ids = list
+            .split_whitespace()
+            .map(|id| IdBreakdown {
+                id,
+                multiplicities: id_analysis(id)
+                    .intersection(&multiplicities)
+                    .cloned()
+                    .collect(),
+            })
+            .collect();
+        ChecksumReport {
+            multiplicities,
+            ids,
+        }
+    }
+
+    /// How many IDs have each multiplicity.
+    pub fn counts(&self) -> BTreeMap<usize, u64> {
+        let mut counts: BTreeMap<usize, u64> =
+            self.multiplicities.iter().map(|&m| (m, 0)).collect();
+        for id in self.ids.iter() {
+            for multiplicity in id.multiplicities.iter() {
+                *counts.get_mut(multiplicity).unwrap() += 1;
+            }
+        }
+        counts
+    }
+
+    /// The product of the counts, or `None` if it overflows.
+    pub fn checksum(&self) -> Option<u64> {
+        self.counts()
+            .values()
+            .try_fold(1u64, |product, &count| product.checked_mul(count))
+    }
+}
+
+impl<'a> fmt::Display for ChecksumReport<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rows = vec![(
+            "id",
+            self.multiplicities
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<_>>(),
+        )];
+        for id in self.ids.iter() {
+            let cells = self.multiplicities.iter().map(|m| {
+                if id.multiplicities.contains(m) {
+                    "1".to_string()
+                } else {
+                    "0".to_string()
+                }
+            });
+            rows.push((id.id, cells.collect()));
+        }
+        let totals = self.counts().values().map(|c| c.to_string()).collect();
+        rows.push(("total", totals));
+
+        let id_width = rows
+            .iter()
+            .map(|(label, _)| graphemes(label).len())
+            .chain(Some(graphemes("checksum").len()))
+            .max()
+            .unwrap();
+        let widths = (0..self.multiplicities.len())
+            .map(|column| {
+                rows.iter()
+                    .map(|(_, cells)| cells[column].len())
+                    .max()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let pad = |f: &mut fmt::Formatter, text: &str| {
+            let padding = id_width - graphemes(text).len();
+            write!(f, "{}{}", text, " ".repeat(padding))
+        };
+
+        for (label, cells) in rows.iter() {
+            pad(f, label)?;
+            for (cell, width) in cells.iter().zip(widths.iter()) {
+                write!(f, " {:>width$}", cell, width = width)?;
+            }
+            writeln!(f)?;
+        }
+        pad(f, "checksum")?;
+        match self.checksum() {
+            Some(checksum) => writeln!(f, " {}", checksum),
+            None => writeln!(f, " overflowed"),
+        }
+    }
+}
+
+/// Splits the text into extended grapheme clusters, which is what a reader
+/// would count as letters.
+pub fn graphemes(text: &str) -> Vec<&str> {
+    UnicodeSegmentation::graphemes(text, true).collect()
 }
 
 pub fn common_letters(list: &str) -> Option<String> {
-    let list_entries: Vec<&str> = list.split_whitespace().collect();
-    for (index, entry_a) in list_entries.iter().enumerate() {
-        for entry_b in list_entries.iter().skip(index + 1) {
-            if hamming_distance_of_one(entry_a, entry_b) {
-                return Some(
-                    entry_a
-                        .chars()
-                        .zip(entry_b.chars())
-                        .filter_map(|(a, b)| if a == b { Some(a) } else { None })
-                        .collect(),
-                );
+    near_duplicates(list)
+        .into_iter()
+        .next()
+        .map(|pair| pair.common_letters)
+}
+
+/// Two IDs that differ by exactly one letter in the same position.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NearDuplicate<'a> {
+    pub first: &'a str,
+    pub second: &'a str,
+    pub common_letters: String,
+}
+
+/// Every pair of IDs that differ by exactly one letter, in the order they
+/// appear in the list.
+pub fn near_duplicates<'a>(list: &'a str) -> Vec<NearDuplicate<'a>> {
+    let entries: Vec<&str> = list.split_whitespace().collect();
+    let letters: Vec<Vec<&str>> = entries
+        .iter()
+        .map(|entry| {
+            entry
+                .char_indices()
+                .map(|(position, letter)| &entry[position..position + letter.len_utf8()])
+                .collect()
+        })
+        .collect();
+    hamming_pairs(&letters, 1)
+        .into_iter()
+        // Repeats of the same ID are no letters apart.
+        .filter(|&(a, b)| letters[a] != letters[b])
+        .map(|(a, b)| NearDuplicate {
+            first: entries[a],
+            second: entries[b],
+            common_letters: letters[a]
+                .iter()
+                .zip(letters[b].iter())
+                .filter(|(a, b)| a == b)
+                .map(|(a, _)| *a)
+                .collect(),
+        })
+        .collect()
+}
+
+/// Every pair of IDs with the same number of letters and no more than
+/// `limit` of them different, in order.
+///
+/// Each ID is indexed by every way of masking out `limit` of its letters.
+/// Two IDs only share such a key if they match everywhere but the masked
+/// positions, so there's no need to compare every pair. There are a lot of
+/// keys for each ID if the limit isn't small.
+fn hamming_pairs(ids: &[Vec<&str>], limit: usize) -> BTreeSet<(usize, usize)> {
+    let mut keys: HashMap<Vec<&str>, Vec<usize>> = HashMap::new();
+    for (index, letters) in ids.iter().enumerate() {
+        for masked in combinations(letters.len(), limit.min(letters.len())) {
+            // Letters are never empty, so this can't match one.
+            let mut key = letters.clone();
+            for position in masked {
+                key[position] = "";
+            }
+            keys.entry(key).or_default().push(index);
+        }
+    }
+
+    let mut pairs = BTreeSet::new();
+    for indices in keys.values() {
+        for (n, &a) in indices.iter().enumerate() {
+            for &b in indices.iter().skip(n + 1) {
+                pairs.insert((a, b));
+            }
+        }
+    }
+    pairs
+}
+
+/// Every way of choosing `k` of the numbers below `n`, in increasing order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut combinations = Vec::new();
+    if k > n {
+        return combinations;
+    }
+    let mut chosen: Vec<usize> = (0..k).collect();
+    loop {
+        combinations.push(chosen.clone());
+        // Advance the last number that has room to, and reset the ones
+        // after it.
+        match (0..k).rev().find(|&i| chosen[i] < n - k + i) {
+            Some(i) => {
+                chosen[i] += 1;
+                for j in i + 1..k {
+                    chosen[j] = chosen[j - 1] + 1;
+                }
             }
+            None => return combinations,
         }
     }
-    None
 }
 
-fn id_analysis(id: &str) -> (bool, bool) {
+fn id_analysis(id: &str) -> BTreeSet<usize> {
     let mut letter_counts = HashMap::new();
-    for letter in id.chars() {
+    for letter in graphemes(id) {
         *letter_counts.entry(letter).or_default() += 1;
     }
-    let counts: HashSet<u8> = HashSet::from_iter(letter_counts.drain().map(|kv| kv.1));
-    (counts.contains(&2), counts.contains(&3))
+    letter_counts.drain().map(|kv| kv.1).collect()
+}
+
+/// How far apart two IDs are, counting letters as graphemes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Distance {
+    /// The number of positions with different letters. IDs of different
+    /// lengths are never close.
+    Hamming,
+    /// The number of letters that have to be inserted, deleted or replaced.
+    Levenshtein,
+}
+
+impl Distance {
+    /// Whether the IDs are no more than `limit` apart.
+    pub fn within(self, a: &str, b: &str, limit: usize) -> bool {
+        self.letters_within(&graphemes(a), &graphemes(b), limit)
+    }
+
+    /// The same as `within`, for IDs already split into graphemes.
+    fn letters_within(self, a: &[&str], b: &[&str], limit: usize) -> bool {
+        match self {
+            Distance::Hamming => {
+                a.len() == b.len()
+                    && a.iter().zip(b.iter()).filter(|(a, b)| a != b).count() <= limit
+            }
+            Distance::Levenshtein => levenshtein_within(a, b, limit),
+        }
+    }
+}
+
+fn levenshtein_within(a: &[&str], b: &[&str], limit: usize) -> bool {
+    if a.len().abs_diff(b.len()) > limit {
+        return false;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, letter_a) in a.iter().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, letter_b) in b.iter().enumerate() {
+            let replace = previous[j] + if letter_a == letter_b { 0 } else { 1 };
+            row.push(replace.min(previous[j + 1] + 1).min(row[j] + 1));
+        }
+        // Distances never go down in later rows.
+        if row.iter().min().unwrap() > &limit {
+            return false;
+        }
+        previous = row;
+    }
+    previous[b.len()] <= limit
+}
+
+/// A group of IDs linked by chains of close pairs.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Cluster<'a> {
+    pub ids: Vec<&'a str>,
+    /// The most common letter at each position, from the IDs of the most
+    /// common length. Ties go to the ID that comes first.
+    pub consensus: String,
+}
+
+impl<'a> Cluster<'a> {
+    fn new(ids: Vec<&'a str>) -> Self {
+        let ids_graphemes = ids.iter().map(|id| graphemes(id)).collect::<Vec<_>>();
+        let mut lengths = BTreeMap::new();
+        for letters in ids_graphemes.iter() {
+            *lengths.entry(letters.len()).or_insert(0) += 1;
+        }
+        let most = lengths.values().max().cloned().unwrap_or(0);
+        let length = ids_graphemes
+            .iter()
+            .map(Vec::len)
+            .find(|length| lengths[length] == most)
+            .unwrap_or(0);
+
+        let consensus = (0..length)
+            .map(|position| {
+                let column = ids_graphemes
+                    .iter()
+                    .filter(|letters| letters.len() == length)
+                    .map(|letters| letters[position])
+                    .collect::<Vec<_>>();
+                let count = |letter| column.iter().filter(|&&l| l == letter).count();
+                let most = column.iter().map(|&l| count(l)).max().unwrap();
+                *column.iter().find(|&&l| count(l) == most).unwrap()
+            })
+            .collect();
+        Cluster { ids, consensus }
+    }
 }
 
+/// Above this, there are too many ways of masking an ID's letters for
+/// indexing them to beat comparing every pair.
+const INDEXED_HAMMING_LIMIT: usize = 2;
+
+/// Groups the IDs that are within `limit` of another ID in the group.
+/// IDs that aren't close to any other are left out.
+///
+/// Small Hamming distances are found through an index of the IDs, like
+/// `near_duplicates`. Otherwise every pair of IDs with compatible lengths is
+/// compared.
+pub fn clusters<'a>(list: &'a str, distance: Distance, limit: usize) -> Vec<Cluster<'a>> {
+    let entries: Vec<&str> = list.split_whitespace().collect();
+    let letters: Vec<Vec<&str>> = entries.iter().map(|id| graphemes(id)).collect();
+    let close_pairs = match distance {
+        Distance::Hamming if limit <= INDEXED_HAMMING_LIMIT => hamming_pairs(&letters, limit),
+        _ => {
+            let mut pairs = BTreeSet::new();
+            for a in 0..entries.len() {
+                for b in a + 1..entries.len() {
+                    let length_difference = letters[a].len().abs_diff(letters[b].len());
+                    let compatible = match distance {
+                        Distance::Hamming => length_difference == 0,
+                        Distance::Levenshtein => length_difference <= limit,
+                    };
+                    if compatible && distance.letters_within(&letters[a], &letters[b], limit) {
+                        pairs.insert((a, b));
+                    }
+                }
+            }
+            pairs
+        }
+    };
+
+    let mut parents: Vec<usize> = (0..entries.len()).collect();
+    let mut linked = vec![false; entries.len()];
+    for (a, b) in close_pairs {
+        let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+        parents[root_a.max(root_b)] = root_a.min(root_b);
+        linked[a] = true;
+        linked[b] = true;
+    }
+
+    let mut groups: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for (index, &entry) in entries.iter().enumerate() {
+        if linked[index] {
+            groups
+                .entry(root(&mut parents, index))
+                .or_default()
+                .push(entry);
+        }
+    }
+    groups.into_values().map(Cluster::new).collect()
+}
+
+fn root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+#[cfg(test)]
 fn hamming_distance_of_one(a: &str, b: &str) -> bool {
     a.chars()
         .zip(b.chars())
@@ -56,12 +400,212 @@ mod checksum {
     #[test]
     fn worked_example() {
         let list = "abcdef bababc abbcde abcccd aabcdd abcdee ababab";
-        assert_eq!(checksum(list), 12);
+        assert_eq!(checksum(list), Some(12));
+    }
+
+    #[test]
+    fn puzzle() {
+        assert_eq!(checksum(include_str!("../input.txt")), Some(7410));
+    }
+}
+
+#[cfg(test)]
+mod checksum_report {
+    use graphemes;
+    use ChecksumReport;
+
+    #[test]
+    fn worked_example() {
+        let list = "abcdef bababc abbcde abcccd aabcdd abcdee ababab";
+        let report = ChecksumReport::new(list, &[2, 3]);
+        assert_eq!(report.checksum(), Some(12));
+        assert_eq!(
+            report.to_string(),
+            "id       2 3
+abcdef   0 0
+bababc   1 1
+abbcde   1 0
+abcccd   0 1
+aabcdd   1 0
+abcdee   1 0
+ababab   0 1
+total    4 3
+checksum 12
+"
+        );
+    }
+
+    #[test]
+    fn multiplicities() {
+        let list = "aaaa aabbbb abcd";
+        let report = ChecksumReport::new(list, &[4, 1, 2, 4]);
+        assert_eq!(
+            report.counts().into_iter().collect::<Vec<_>>(),
+            vec![(1, 1), (2, 1), (4, 2)]
+        );
+        assert_eq!(report.checksum(), Some(2));
+        assert_eq!(ChecksumReport::new(list, &[]).checksum(), Some(1));
+        assert_eq!(ChecksumReport::new(list, &[5]).checksum(), Some(0));
+    }
+
+    #[test]
+    fn graphemes_not_chars() {
+        // An e with a combining acute accent, twice, and an e on its own.
+        let list = "e\u{301}xe\u{301}e";
+        assert_eq!(graphemes(list), vec!["e\u{301}", "x", "e\u{301}", "e"]);
+        let report = ChecksumReport::new(list, &[1, 2, 3]);
+        assert_eq!(
+            report.ids[0].multiplicities.iter().collect::<Vec<_>>(),
+            vec![&1, &2]
+        );
+        assert_eq!(
+            report.to_string().lines().nth(1),
+            Some("e\u{301}xe\u{301}e     1 1 0")
+        );
+    }
+
+    #[test]
+    fn emoji() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let waving = "\u{1F44B}\u{1F3FD}";
+        assert_eq!(
+            graphemes(&format!("{}a{}", family, waving)),
+            vec![family, "a", waving]
+        );
+        assert_eq!(graphemes(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn other_scripts() {
+        let flag = "\u{1F1EC}\u{1F1E7}";
+        assert_eq!(graphemes(flag), vec![flag]);
+        // Devanagari: a conjunct with a vowel sign, then a consonant.
+        assert_eq!(
+            graphemes("\u{915}\u{94D}\u{937}\u{93F}\u{915}"),
+            vec!["\u{915}\u{94D}\u{937}\u{93F}", "\u{915}"]
+        );
+        // Hangul jamo making up a single syllable.
+        assert_eq!(graphemes("\u{1100}\u{1161}\u{11A8}").len(), 1);
+    }
+
+    #[test]
+    fn wide_columns() {
+        let list = "aab ".repeat(12);
+        let report = ChecksumReport::new(&list, &[2, 3]);
+        let table = report.to_string();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "id        2 3");
+        assert_eq!(lines[1], "aab       1 0");
+        assert_eq!(lines[13], "total    12 0");
+        assert_eq!(lines[14], "checksum 0");
+    }
+}
+
+#[cfg(test)]
+mod clusters {
+    use clusters;
+    use combinations;
+    use graphemes;
+    use hamming_pairs;
+    use near_duplicates;
+    use std::collections::BTreeSet;
+    use Cluster;
+    use Distance;
+    use INDEXED_HAMMING_LIMIT;
+
+    #[test]
+    fn distance() {
+        assert!(Distance::Hamming.within("abcde", "axcye", 2));
+        assert!(!Distance::Hamming.within("abcde", "axcye", 1));
+        assert!(!Distance::Hamming.within("abcde", "abcd", 5));
+        assert!(Distance::Levenshtein.within("abcde", "abde", 1));
+        assert!(Distance::Levenshtein.within("kitten", "sitting", 3));
+        assert!(!Distance::Levenshtein.within("kitten", "sitting", 2));
+        assert!(Distance::Levenshtein.within("", "ab", 2));
+        assert!(Distance::Levenshtein.within("e\u{301}b", "eb", 1));
+    }
+
+    #[test]
+    fn worked_example() {
+        let list = "abcde fghij klmno pqrst fguij axcye wvxyz";
+        assert_eq!(
+            clusters(list, Distance::Hamming, 1),
+            vec![Cluster {
+                ids: vec!["fghij", "fguij"],
+                consensus: "fghij".to_string()
+            }]
+        );
+        assert_eq!(
+            clusters(list, Distance::Hamming, 2),
+            vec![
+                Cluster {
+                    ids: vec!["abcde", "axcye"],
+                    consensus: "abcde".to_string()
+                },
+                Cluster {
+                    ids: vec!["fghij", "fguij"],
+                    consensus: "fghij".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn chains() {
+        let list = "axxxx abxxx xbxxy bbxxy abcde abcd abzcd";
+        let ids = |clusters: Vec<Cluster<'static>>| {
+            clusters
+                .into_iter()
+                .map(|c| (c.ids, c.consensus))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(clusters(list, Distance::Hamming, 1)),
+            vec![
+                (vec!["axxxx", "abxxx"], "axxxx".to_string()),
+                (vec!["xbxxy", "bbxxy"], "xbxxy".to_string()),
+            ]
+        );
+        assert_eq!(
+            ids(clusters(list, Distance::Hamming, 2)),
+            vec![(
+                vec!["axxxx", "abxxx", "xbxxy", "bbxxy"],
+                "abxxx".to_string()
+            )]
+        );
+        assert_eq!(
+            ids(clusters(list, Distance::Levenshtein, 1)),
+            vec![
+                (vec!["axxxx", "abxxx"], "axxxx".to_string()),
+                (vec!["xbxxy", "bbxxy"], "xbxxy".to_string()),
+                (vec!["abcde", "abcd", "abzcd"], "abcde".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn indexed_matches_every_pair() {
+        let list = "abcd abce abde xbde abcd zzzz zzyy zyzy e\u{301}bcd ebcd yyyy abc";
+        let letters = list.split_whitespace().map(graphemes).collect::<Vec<_>>();
+        for limit in 0..=INDEXED_HAMMING_LIMIT + 2 {
+            let every_pair = (0..letters.len())
+                .flat_map(|a| (a + 1..letters.len()).map(move |b| (a, b)))
+                .filter(|&(a, b)| Distance::Hamming.letters_within(&letters[a], &letters[b], limit))
+                .collect::<BTreeSet<_>>();
+            assert_eq!(hamming_pairs(&letters, limit), every_pair);
+        }
+        assert_eq!(combinations(4, 2).len(), 6);
+        assert_eq!(combinations(2, 0), vec![Vec::<usize>::new()]);
+        assert_eq!(combinations(2, 3), Vec::<Vec<usize>>::new());
     }
 
     #[test]
     fn puzzle() {
-        assert_eq!(checksum(include_str!("../input.txt")), 7410);
+        let list = include_str!("../input.txt");
+        let pairs = near_duplicates(list);
+        let clusters = clusters(list, Distance::Hamming, 1);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].ids, vec![pairs[0].first, pairs[0].second]);
     }
 }
 
@@ -83,3 +627,63 @@ mod common_letters {
         );
     }
 }
+
+#[cfg(test)]
+mod near_duplicates {
+    use hamming_distance_of_one;
+    use near_duplicates;
+    use NearDuplicate;
+
+    #[test]
+    fn worked_example() {
+        let list = "abcde fghij klmno pqrst fguij axcye wvxyz";
+        assert_eq!(
+            near_duplicates(list),
+            vec![NearDuplicate {
+                first: "fghij",
+                second: "fguij",
+                common_letters: "fgij".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn every_pair() {
+        let list = "abc xbc abd abc ab abcd äbc";
+        let pairs = near_duplicates(list)
+            .into_iter()
+            .map(|pair| (pair.first, pair.second, pair.common_letters))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                ("abc", "xbc", "bc".to_string()),
+                ("abc", "abd", "ab".to_string()),
+                ("abc", "äbc", "bc".to_string()),
+                ("xbc", "abc", "bc".to_string()),
+                ("xbc", "äbc", "bc".to_string()),
+                ("abd", "abc", "ab".to_string()),
+                ("abc", "äbc", "bc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn puzzle() {
+        let list = include_str!("../input.txt");
+        let pairs = near_duplicates(list);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].common_letters, "cnjxoritzhvbosyewrmqhgkul");
+
+        let entries = list.split_whitespace().collect::<Vec<_>>();
+        let mut brute_force = 0;
+        for (index, a) in entries.iter().enumerate() {
+            for b in entries.iter().skip(index + 1) {
+                if hamming_distance_of_one(a, b) {
+                    brute_force += 1;
+                }
+            }
+        }
+        assert_eq!(brute_force, pairs.len());
+    }
+}
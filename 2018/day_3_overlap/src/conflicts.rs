@@ -0,0 +1,83 @@
+use claims;
+use parse::ParseError;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use sweep::overlapping_pairs;
+use Claim;
+
+/// Another claim that shares some fabric.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Conflict {
+    pub id: u16,
+    /// The square inches both claims want.
+    pub area: usize,
+}
+
+/// Which claims overlap which.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ConflictGraph {
+    /// The conflicts of every claim, in order of the other claim's ID.
+    /// Claims without any conflicts have an empty list.
+    pub conflicts: BTreeMap<u16, Vec<Conflict>>,
+}
+
+impl ConflictGraph {
+    /// Fails at the first line that isn't a claim.
+    pub fn new(input: &str) -> Result<Self, ParseError> {
+        Ok(Self::from_claims(&claims(input)?))
+    }
+
+    pub fn from_claims(claims: &[Claim]) -> Self {
+        let mut conflicts: BTreeMap<u16, Vec<Conflict>> =
+            claims.iter().map(|claim| (claim.id, Vec::new())).collect();
+        for (a, b) in overlapping_pairs(claims) {
+            let (a, b) = (&claims[a], &claims[b]);
+            let area = a.intersection_area(b);
+            conflicts
+                .get_mut(&a.id)
+                .unwrap()
+                .push(Conflict { id: b.id, area });
+            conflicts
+                .get_mut(&b.id)
+                .unwrap()
+                .push(Conflict { id: a.id, area });
+        }
+        for list in conflicts.values_mut() {
+            list.sort_by_key(|conflict| conflict.id);
+        }
+        ConflictGraph { conflicts }
+    }
+
+    /// The claims that don't overlap any other.
+    pub fn intact<'a>(&'a self) -> impl Iterator<Item = u16> + 'a {
+        self.conflicts
+            .iter()
+            .filter(|(_, conflicts)| conflicts.is_empty())
+            .map(|(&id, _)| id)
+    }
+
+    /// Groups of claims linked by chains of conflicts, each in order of ID.
+    /// A claim without conflicts is a group on its own.
+    pub fn components(&self) -> Vec<Vec<u16>> {
+        let mut seen = BTreeSet::new();
+        let mut components = Vec::new();
+        for &start in self.conflicts.keys() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut component = vec![start];
+            let mut to_visit = vec![start];
+            while let Some(id) = to_visit.pop() {
+                for conflict in self.conflicts[&id].iter() {
+                    if seen.insert(conflict.id) {
+                        component.push(conflict.id);
+                        to_visit.push(conflict.id);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        components
+    }
+}
@@ -0,0 +1,159 @@
+use claims;
+use parse::ParseError;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use sweep::covered_areas;
+use sweep::overlap_area;
+use sweep::overlapping_pairs;
+use Claim;
+
+/// Claims that can be edited one at a time, keeping the overlapping area
+/// and the intact claims up to date as they go.
+///
+/// Claims are indexed by their left edge, so an edit only looks at the
+/// claims starting less than the widest claim's width to its left. The
+/// cost depends on how crowded that band of the fabric is rather than on
+/// how many claims there are.
+#[derive(Debug, Clone, Default)]
+pub struct Fabric {
+    claims: BTreeMap<u16, Claim>,
+    /// The claims' left edges and IDs.
+    by_left: BTreeSet<(u32, u16)>,
+    /// At least as wide as the widest claim. It doesn't shrink when claims
+    /// are removed.
+    widest: u32,
+    /// How many other claims overlap each claim.
+    conflicts: BTreeMap<u16, usize>,
+    overlap: usize,
+}
+
+impl Fabric {
+    /// The claims in the input, failing at the first line that isn't a
+    /// claim. If more than one claim has the same ID, only the first is
+    /// kept.
+    pub fn new(input: &str) -> Result<Self, ParseError> {
+        Ok(Self::from_claims(claims(input)?))
+    }
+
+    pub fn from_claims<I: IntoIterator<Item = Claim>>(claims: I) -> Self {
+        let mut claims_by_id = BTreeMap::new();
+        for claim in claims {
+            claims_by_id.entry(claim.id).or_insert(claim);
+        }
+        let claims = claims_by_id.values().cloned().collect::<Vec<_>>();
+        let mut conflicts: BTreeMap<u16, usize> =
+            claims.iter().map(|claim| (claim.id, 0)).collect();
+        for (a, b) in overlapping_pairs(&claims) {
+            *conflicts.get_mut(&claims[a].id).unwrap() += 1;
+            *conflicts.get_mut(&claims[b].id).unwrap() += 1;
+        }
+        Fabric {
+            overlap: overlap_area(&claims),
+            by_left: claims
+                .iter()
+                .map(|claim| (claim.left(), claim.id))
+                .collect(),
+            widest: claims
+                .iter()
+                .map(|claim| u32::from(claim.width))
+                .max()
+                .unwrap_or(0),
+            claims: claims_by_id,
+            conflicts,
+        }
+    }
+
+    /// The square inches within two or more claims.
+    pub fn overlap(&self) -> usize {
+        self.overlap
+    }
+
+    /// The claims that don't overlap any other, in order of ID.
+    pub fn intact<'a>(&'a self) -> impl Iterator<Item = u16> + 'a {
+        self.conflicts
+            .iter()
+            .filter(|(_, &count)| count == 0)
+            .map(|(&id, _)| id)
+    }
+
+    pub fn contains(&self, id: u16) -> bool {
+        self.claims.contains_key(&id)
+    }
+
+    /// Returns `false`, without changing anything, if there's already a
+    /// claim with the ID.
+    pub fn add(&mut self, id: u16, from_left: u16, from_top: u16, width: u16, height: u16) -> bool {
+        if self.contains(id) {
+            return false;
+        }
+        let claim = Claim {
+            id,
+            from_left,
+            from_top,
+            width,
+            height,
+        };
+        self.conflicts.insert(id, 0);
+        self.update(&claim, true);
+        self.by_left.insert((claim.left(), id));
+        self.widest = self.widest.max(u32::from(width));
+        self.claims.insert(id, claim);
+        true
+    }
+
+    /// Returns `false` if there's no claim with the ID.
+    pub fn remove(&mut self, id: u16) -> bool {
+        match self.claims.remove(&id) {
+            Some(claim) => {
+                self.by_left.remove(&(claim.left(), id));
+                self.update(&claim, false);
+                self.conflicts.remove(&id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Changes the size of a claim, keeping its top left corner. Returns
+    /// `false` if there's no claim with the ID.
+    pub fn resize(&mut self, id: u16, width: u16, height: u16) -> bool {
+        match self.claims.get(&id).cloned() {
+            Some(claim) => {
+                self.remove(id);
+                self.add(id, claim.from_left, claim.from_top, width, height)
+            }
+            None => false,
+        }
+    }
+
+    /// Accounts for a claim being added to or removed from the other
+    /// claims.
+    fn update(&mut self, claim: &Claim, adding: bool) {
+        // Anything starting further left than this is too narrow to reach.
+        let reach = claim.left().saturating_sub(self.widest);
+        let within = self
+            .by_left
+            .range((reach, 0)..(claim.right(), 0))
+            .filter(|&&(_, id)| id != claim.id)
+            .filter_map(|(_, id)| claim.intersection(&self.claims[id]))
+            .collect::<Vec<_>>();
+        // The inches already claimed by exactly one other claim are the
+        // ones that start or stop overlapping.
+        let (once, twice) = covered_areas(&within);
+        let change = once - twice;
+        for other in within.iter() {
+            let count = self.conflicts.get_mut(&other.id).unwrap();
+            if adding {
+                *count += 1;
+            } else {
+                *count -= 1;
+            }
+        }
+        if adding {
+            self.overlap += change;
+            self.conflicts.insert(claim.id, within.len());
+        } else {
+            self.overlap -= change;
+        }
+    }
+}
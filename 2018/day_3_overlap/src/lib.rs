@@ -1,26 +1,140 @@
+extern crate image;
 #[macro_use]
 extern crate nom;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+pub mod conflicts;
+pub mod fabric;
+pub mod parse;
+mod sweep;
 
+use parse::Format;
+use parse::ParseError;
+#[cfg(test)]
 use std::collections::HashMap;
-use std::str::FromStr;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
 
-pub fn overlap(input: &str) -> usize {
-    used_cloth(input).values().filter(|&&s| s).count()
+pub fn overlap(input: &str) -> Result<usize, ParseError> {
+    Ok(sweep::overlap_area(&claims(input)?))
 }
 
-pub fn no_overlap(input: &str) -> Option<u16> {
-    let used_cloth = used_cloth(input);
-    claims(input)
-        .find(|claim| {
-            claim
-                .cords()
-                .all(|cord| used_cloth.get(&cord) == Some(&false))
-        }).map(|claim| claim.id)
+pub fn no_overlap(input: &str) -> Result<Option<u16>, ParseError> {
+    let claims = claims(input)?;
+    Ok(first_intact(&claims).map(|i| claims[i].id))
 }
 
+fn first_intact(claims: &[Claim]) -> Option<usize> {
+    let overlapping: HashSet<usize> = sweep::overlapping_pairs(claims)
+        .into_iter()
+        .flat_map(|(a, b)| vec![a, b])
+        .collect();
+    (0..claims.len()).find(|i| !overlapping.contains(i))
+}
+
+/// The most square inches `heatmap` will render, which is 4096 by 4096.
+pub const HEATMAP_LIMIT: usize = 1 << 24;
+
+/// The fabric as an image, where the more claims cover an inch the brighter
+/// it is. The claim found by `no_overlap` is green.
+///
+/// The image starts at the top left corner of the fabric, so fails if the
+/// claims reach further than `HEATMAP_LIMIT` square inches would.
+pub fn heatmap(input: &str) -> Result<image::RgbImage, HeatmapError> {
+    let claims = claims(input)?;
+    let width = claims.iter().map(Claim::right).max().unwrap_or(0);
+    let height = claims.iter().map(Claim::bottom).max().unwrap_or(0);
+    // The corners along the right and bottom edges need a row and column
+    // of their own.
+    let size = (width as usize + 1)
+        .checked_mul(height as usize + 1)
+        .filter(|&size| size <= HEATMAP_LIMIT)
+        .ok_or(HeatmapError::TooLarge { width, height })?;
+    let index = |x: u32, y: u32| y as usize * (width as usize + 1) + x as usize;
+
+    // Mark the corners of each claim, so adding up everything above and to
+    // the left of an inch counts the claims covering it.
+    let mut coverage = vec![0i32; size];
+    for claim in claims.iter() {
+        coverage[index(claim.left(), claim.top())] += 1;
+        coverage[index(claim.right(), claim.top())] -= 1;
+        coverage[index(claim.left(), claim.bottom())] -= 1;
+        coverage[index(claim.right(), claim.bottom())] += 1;
+    }
+    for y in 0..=height {
+        for x in 0..=width {
+            let left = if x > 0 { coverage[index(x - 1, y)] } else { 0 };
+            let above = if y > 0 { coverage[index(x, y - 1)] } else { 0 };
+            let diagonal = if x > 0 && y > 0 {
+                coverage[index(x - 1, y - 1)]
+            } else {
+                0
+            };
+            coverage[index(x, y)] += left + above - diagonal;
+        }
+    }
+
+    let most = coverage.iter().cloned().max().unwrap_or(0).max(1);
+    let intact = first_intact(&claims).map(|i| &claims[i]);
+    Ok(image::ImageBuffer::from_fn(width, height, |x, y| {
+        let in_intact = intact.is_some_and(|claim| {
+            claim.left() <= x && x < claim.right() && claim.top() <= y && y < claim.bottom()
+        });
+        if in_intact {
+            image::Rgb([0u8, 255u8, 0u8])
+        } else {
+            image::Rgb([(255 * coverage[index(x, y)] / most) as u8; 3])
+        }
+    }))
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum HeatmapError {
+    Parse(ParseError),
+    /// The claims cover too much of the fabric to render.
+    TooLarge {
+        width: u32,
+        height: u32,
+    },
+}
+
+impl From<ParseError> for HeatmapError {
+    fn from(error: ParseError) -> Self {
+        HeatmapError::Parse(error)
+    }
+}
+
+impl fmt::Display for HeatmapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeatmapError::Parse(error) => error.fmt(f),
+            HeatmapError::TooLarge { width, height } => write!(
+                f,
+                "the claims reach {} by {} inches, which is too large to render",
+                width, height
+            ),
+        }
+    }
+}
+
+impl Error for HeatmapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HeatmapError::Parse(error) => Some(error),
+            HeatmapError::TooLarge { .. } => None,
+        }
+    }
+}
+
+/// Every square inch of fabric that's claimed, and whether it's claimed more
+/// than once. The sweep is checked against this.
+#[cfg(test)]
 fn used_cloth(input: &str) -> HashMap<(u16, u16), bool> {
     let mut used_cloth = HashMap::with_capacity(1000 * 1000);
-    for claim in claims(input) {
+    for claim in claims(input).unwrap() {
         for (x, y) in claim.cords() {
             used_cloth
                 .entry((x, y))
@@ -31,58 +145,85 @@ fn used_cloth(input: &str) -> HashMap<(u16, u16), bool> {
     used_cloth
 }
 
-#[derive(PartialEq, Debug)]
-struct Claim {
-    id: u16,
-    from_left: u16,
-    from_top: u16,
-    width: u16,
-    height: u16,
+/// Repeatable random numbers below a limit, for the tests. It's xorshift,
+/// so the tests don't need a dependency.
+#[cfg(test)]
+fn random_numbers() -> impl FnMut(u16) -> u16 {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    move |limit| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % u64::from(limit)) as u16
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+pub struct Claim {
+    pub id: u16,
+    #[serde(rename = "left")]
+    pub from_left: u16,
+    #[serde(rename = "top")]
+    pub from_top: u16,
+    pub width: u16,
+    pub height: u16,
 }
 
-impl<'a> Claim {
-    fn cords(&'a self) -> impl Iterator<Item = (u16, u16)> + 'a {
+impl Claim {
+    #[cfg(test)]
+    fn cords<'a>(&'a self) -> impl Iterator<Item = (u16, u16)> + 'a {
         (self.from_left..self.from_left + self.width)
             .flat_map(move |x| (self.from_top..self.from_top + self.height).map(move |y| (x, y)))
     }
-}
 
-named!(claim<&str,Claim>,
-    do_parse!(
-        tag_s!("#") >>
-        id: map_res!(nom::digit, FromStr::from_str) >>
-        ws!(tag_s!("@")) >>
-        from_left: map_res!(nom::digit, FromStr::from_str) >>
-        tag_s!(",") >>
-        from_top: map_res!(nom::digit, FromStr::from_str) >>
-        ws!(tag_s!(":")) >>
-        width: map_res!(nom::digit, FromStr::from_str) >>
-        tag_s!("x") >>
-        height: map_res!(nom::digit, FromStr::from_str) >>
+    fn left(&self) -> u32 {
+        u32::from(self.from_left)
+    }
 
-        (Claim{ id, from_left, from_top, width, height})
-    )
-);
+    fn right(&self) -> u32 {
+        self.left() + u32::from(self.width)
+    }
 
-struct ClaimsIterator<'a> {
-    input: &'a str,
-}
+    fn top(&self) -> u32 {
+        u32::from(self.from_top)
+    }
 
-impl<'a> Iterator for ClaimsIterator<'a> {
-    type Item = Claim;
-    fn next(&mut self) -> Option<Self::Item> {
-        match claim(self.input.trim_start()) {
-            Ok((remaining, claim)) => {
-                self.input = remaining;
-                Some(claim)
-            }
-            Err(_) => None,
+    fn bottom(&self) -> u32 {
+        self.top() + u32::from(self.height)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    fn intersection_area(&self, other: &Claim) -> usize {
+        self.intersection(other)
+            .map_or(0, |claim| claim.width as usize * claim.height as usize)
+    }
+
+    /// The part of the other claim that's within this one, as a claim with
+    /// the other's ID.
+    fn intersection(&self, other: &Claim) -> Option<Claim> {
+        let from_left = self.left().max(other.left());
+        let from_top = self.top().max(other.top());
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= from_left || bottom <= from_top {
+            return None;
         }
+        Some(Claim {
+            id: other.id,
+            from_left: from_left as u16,
+            from_top: from_top as u16,
+            width: (right - from_left) as u16,
+            height: (bottom - from_top) as u16,
+        })
     }
 }
 
-fn claims<'a>(input: &'a str) -> impl Iterator<Item = Claim> + 'a {
-    ClaimsIterator { input }
+/// The claims in the input, failing at the first line that isn't a claim.
+fn claims(input: &str) -> Result<Vec<Claim>, ParseError> {
+    parse::parse_claims(input, Format::Claims)
 }
 
 #[cfg(test)]
@@ -95,14 +236,13 @@ mod overlap_tests {
             #2 @ 3,1: 4x4
             #3 @ 5,5: 2x2
         "#;
-        assert_eq!(overlap(input), 4);
+        assert_eq!(overlap(input), Ok(4));
     }
 
     #[test]
     fn puzzle() {
-        assert_eq!(overlap(include_str!("../input.txt")), 100595);
+        assert_eq!(overlap(include_str!("../input.txt")), Ok(100595));
     }
-
 }
 
 #[cfg(test)]
@@ -115,11 +255,492 @@ mod no_overlap_tests {
             #2 @ 3,1: 4x4
             #3 @ 5,5: 2x2
         "#;
-        assert_eq!(no_overlap(input), Some(3));
+        assert_eq!(no_overlap(input), Ok(Some(3)));
     }
 
     #[test]
     fn puzzle() {
-        assert_eq!(no_overlap(include_str!("../input.txt")), Some(415));
+        assert_eq!(no_overlap(include_str!("../input.txt")), Ok(Some(415)));
+    }
+}
+
+#[cfg(test)]
+mod heatmap_tests {
+    extern crate image;
+    use heatmap;
+    use HeatmapError;
+    use HEATMAP_LIMIT;
+
+    #[test]
+    fn worked_example() {
+        let input = r#"
+            #1 @ 1,3: 4x4
+            #2 @ 3,1: 4x4
+            #3 @ 5,5: 2x2
+        "#;
+        assert_eq!(
+            heatmap(input).unwrap().into_vec(),
+            image::load_from_memory(include_bytes!("../worked_examples/heatmap.png"))
+                .unwrap()
+                .raw_pixels()
+        );
+    }
+
+    #[test]
+    fn dimensions() {
+        assert_eq!(heatmap("").unwrap().dimensions(), (0, 0));
+        let image = heatmap("#1 @ 2,1: 3x4\n").unwrap();
+        assert_eq!(image.dimensions(), (5, 5));
+        assert_eq!(image.get_pixel(0, 0), &image::Rgb([0, 0, 0]));
+        assert_eq!(image.get_pixel(2, 1), &image::Rgb([0, 255, 0]));
+    }
+
+    #[test]
+    fn too_large() {
+        assert_eq!(
+            heatmap("#1 @ 65535,65535: 65535x65535\n").err(),
+            Some(HeatmapError::TooLarge {
+                width: 131070,
+                height: 131070
+            })
+        );
+        let error = heatmap("#1 @ 60000,60000: 5000x5000\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the claims reach 65000 by 65000 inches, which is too large to render"
+        );
+        // A claim near the far corner is small, but the image starts at the
+        // top left.
+        assert!(heatmap("#1 @ 65534,65534: 1x1\n").is_err());
+
+        // Right up to the limit, counting the extra row and column.
+        let side = (HEATMAP_LIMIT as f64).sqrt() as u32 - 1;
+        let image = heatmap(&format!("#1 @ {},{}: 1x1\n", side - 1, side - 1)).unwrap();
+        assert_eq!(image.dimensions(), (side, side));
+        assert_eq!(
+            image.get_pixel(side - 1, side - 1),
+            &image::Rgb([0, 255, 0])
+        );
+        assert!(heatmap(&format!("#1 @ {},{}: 1x1\n", side, side - 1)).is_err());
+    }
+
+    #[test]
+    fn puzzle() {
+        assert_eq!(
+            heatmap(include_str!("../input.txt")).unwrap().into_vec(),
+            image::load_from_memory(include_bytes!("../puzzle/heatmap.png"))
+                .unwrap()
+                .raw_pixels()
+        );
+    }
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use conflicts::ConflictGraph;
+    use fabric::Fabric;
+    use heatmap;
+    use no_overlap;
+    use overlap;
+    use parse::parse_claims;
+    use parse::Format;
+    use parse::ParseError;
+    use Claim;
+    use HeatmapError;
+
+    fn error(line: usize, column: usize, text: &str, message: &str) -> ParseError {
+        ParseError {
+            line,
+            column,
+            text: text.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn worked_example() -> Vec<Claim> {
+        vec![
+            Claim {
+                id: 1,
+                from_left: 1,
+                from_top: 3,
+                width: 4,
+                height: 4,
+            },
+            Claim {
+                id: 2,
+                from_left: 3,
+                from_top: 1,
+                width: 4,
+                height: 4,
+            },
+            Claim {
+                id: 3,
+                from_left: 5,
+                from_top: 5,
+                width: 2,
+                height: 2,
+            },
+        ]
+    }
+
+    #[test]
+    fn claims() {
+        let input = "#1 @ 1,3: 4x4\n\n  #2 @ 3,1: 4x4\n#3 @ 5,5: 2x2";
+        assert_eq!(Format::detect(input), Format::Claims);
+        assert_eq!(parse_claims(input, Format::Claims), Ok(worked_example()));
+    }
+
+    #[test]
+    fn claim_errors() {
+        let input = "#1 @ 1,3: 4x4\n  #2 @ x,1: 4x4\n#3 @ 5,5: 2x2";
+        assert_eq!(
+            parse_claims(input, Format::Claims),
+            Err(error(
+                2,
+                8,
+                "#2 @ x,1: 4x4",
+                "expected a claim like `#1 @ 1,3: 4x4`"
+            ))
+        );
+        assert_eq!(
+            parse_claims("#1 @ 1,3: 4x4 please", Format::Claims),
+            Err(error(
+                1,
+                14,
+                "#1 @ 1,3: 4x4 please",
+                "unexpected text after the claim"
+            ))
+        );
+        assert_eq!(
+            parse_claims("#1 @ 1,3: 4x", Format::Claims),
+            Err(error(
+                1,
+                13,
+                "#1 @ 1,3: 4x",
+                "expected a claim like `#1 @ 1,3: 4x4`"
+            ))
+        );
+        assert_eq!(
+            parse_claims("#1 @ 1,3: 99999x4", Format::Claims).map_err(|e| e.column),
+            Err(11)
+        );
+        assert_eq!(
+            error(2, 8, "#2 @ x,1: 4x4", "expected a number").to_string(),
+            "line 2, column 8: expected a number: #2 @ x,1: 4x4"
+        );
+        // The entry points report the bad line rather than skipping it.
+        let bad_line = error(
+            2,
+            8,
+            "#2 @ x,1: 4x4",
+            "expected a claim like `#1 @ 1,3: 4x4`",
+        );
+        assert_eq!(overlap(input), Err(bad_line.clone()));
+        assert_eq!(no_overlap(input).map_err(|e| e.line), Err(2));
+        assert_eq!(heatmap(input).err(), Some(HeatmapError::Parse(bad_line)));
+        assert_eq!(Fabric::new(input).map_err(|e| e.line).err(), Some(2));
+        assert_eq!(ConflictGraph::new(input).map_err(|e| e.line), Err(2));
+    }
+
+    #[test]
+    fn csv() {
+        let input = "id, left, top, width, height\n1,1,3,4,4\n2, 3, 1, 4, 4\n\n3,5,5,2,2\n";
+        assert_eq!(Format::detect(input), Format::Csv);
+        assert_eq!(parse_claims(input, Format::Csv), Ok(worked_example()));
+        assert_eq!(
+            parse_claims("1,1,3,4,4\n2, 3, one, 4, 4", Format::Csv),
+            Err(error(2, 7, "2, 3, one, 4, 4", "expected a number"))
+        );
+        assert_eq!(
+            parse_claims("1,1,3,4", Format::Csv),
+            Err(error(1, 8, "1,1,3,4", "expected 5 fields"))
+        );
+        assert_eq!(
+            parse_claims("1,1,3,4,4,5", Format::Csv),
+            Err(error(1, 11, "1,1,3,4,4,5", "expected 5 fields"))
+        );
+    }
+
+    #[test]
+    fn json() {
+        let input = r#"[
+            {"id": 1, "left": 1, "top": 3, "width": 4, "height": 4},
+            {"id": 2, "left": 3, "top": 1, "width": 4, "height": 4},
+            {"id": 3, "left": 5, "top": 5, "width": 2, "height": 2}
+        ]"#;
+        assert_eq!(Format::detect(input), Format::Json);
+        let claims = parse_claims(input, Format::Json).unwrap();
+        assert_eq!(claims, worked_example());
+        assert_eq!(Fabric::from_claims(claims.clone()).overlap(), 4);
+        assert_eq!(
+            ConflictGraph::from_claims(&claims)
+                .intact()
+                .collect::<Vec<_>>(),
+            vec![3]
+        );
+
+        let input = "[\n  {\"id\": 1, \"left\": 1, \"top\": 3, \"width\": 4}\n]";
+        assert_eq!(
+            parse_claims(input, Format::Json),
+            Err(error(
+                2,
+                44,
+                r#"{"id": 1, "left": 1, "top": 3, "width": 4}"#,
+                "missing field `height`"
+            ))
+        );
+    }
+
+    #[test]
+    fn puzzle() {
+        let claims = parse_claims(include_str!("../input.txt"), Format::Claims).unwrap();
+        assert_eq!(claims.len(), 1237);
+    }
+}
+
+#[cfg(test)]
+mod fabric_tests {
+    use fabric::Fabric;
+    use no_overlap;
+    use overlap;
+    use random_numbers;
+
+    const WORKED_EXAMPLE: &str = r#"
+            #1 @ 1,3: 4x4
+            #2 @ 3,1: 4x4
+            #3 @ 5,5: 2x2
+        "#;
+
+    #[test]
+    fn worked_example() {
+        let mut fabric = Fabric::new(WORKED_EXAMPLE).unwrap();
+        assert_eq!(fabric.overlap(), 4);
+        assert_eq!(fabric.intact().collect::<Vec<_>>(), vec![3]);
+
+        assert!(fabric.add(4, 4, 4, 2, 2));
+        assert!(!fabric.add(4, 0, 0, 1, 1));
+        assert_eq!(fabric.overlap(), 7);
+        assert_eq!(fabric.intact().count(), 0);
+
+        assert!(fabric.resize(4, 1, 1));
+        assert_eq!(fabric.overlap(), 4);
+        assert_eq!(fabric.intact().collect::<Vec<_>>(), vec![3]);
+
+        assert!(fabric.remove(1));
+        assert!(!fabric.remove(1));
+        assert!(!fabric.resize(1, 1, 1));
+        assert_eq!(fabric.overlap(), 1);
+        assert_eq!(fabric.intact().collect::<Vec<_>>(), vec![3]);
+
+        assert!(fabric.remove(4));
+        assert_eq!(fabric.overlap(), 0);
+        assert_eq!(fabric.intact().collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn edits_match_recalculating() {
+        let mut next = random_numbers();
+        let mut fabric = Fabric::default();
+        let mut claims = std::collections::BTreeMap::new();
+        for _ in 0..400 {
+            let id = next(40);
+            let claim = (next(30), next(30), next(10), next(10));
+            match next(3) {
+                0 => {
+                    let added = fabric.add(id, claim.0, claim.1, claim.2, claim.3);
+                    assert_eq!(added, !claims.contains_key(&id));
+                    claims.entry(id).or_insert(claim);
+                }
+                1 => assert_eq!(fabric.remove(id), claims.remove(&id).is_some()),
+                _ => {
+                    assert_eq!(
+                        fabric.resize(id, claim.2, claim.3),
+                        claims.contains_key(&id)
+                    );
+                    if let Some(existing) = claims.get_mut(&id) {
+                        existing.2 = claim.2;
+                        existing.3 = claim.3;
+                    }
+                }
+            }
+
+            let input = claims
+                .iter()
+                .map(|(id, c)| format!("#{} @ {},{}: {}x{}\n", id, c.0, c.1, c.2, c.3))
+                .collect::<String>();
+            let recalculated = Fabric::new(&input).unwrap();
+            assert_eq!(Ok(fabric.overlap()), overlap(&input));
+            assert_eq!(
+                fabric.intact().collect::<Vec<_>>(),
+                recalculated.intact().collect::<Vec<_>>()
+            );
+            assert_eq!(Ok(fabric.intact().next()), no_overlap(&input));
+        }
+    }
+
+    #[test]
+    fn puzzle() {
+        let fabric = Fabric::new(include_str!("../input.txt")).unwrap();
+        assert_eq!(fabric.overlap(), 100595);
+        assert_eq!(fabric.intact().collect::<Vec<_>>(), vec![415]);
+    }
+}
+
+#[cfg(test)]
+mod conflict_graph_tests {
+    use conflicts::Conflict;
+    use conflicts::ConflictGraph;
+    use overlap;
+
+    #[test]
+    fn worked_example() {
+        let graph = ConflictGraph::new(
+            r#"
+            #1 @ 1,3: 4x4
+            #2 @ 3,1: 4x4
+            #3 @ 5,5: 2x2
+        "#,
+        )
+        .unwrap();
+        assert_eq!(
+            graph.conflicts.into_iter().collect::<Vec<_>>(),
+            vec![
+                (1, vec![Conflict { id: 2, area: 4 }]),
+                (2, vec![Conflict { id: 1, area: 4 }]),
+                (3, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn components() {
+        let graph = ConflictGraph::new(
+            r#"
+            #1 @ 0,0: 2x2
+            #2 @ 5,5: 2x2
+            #3 @ 1,1: 3x3
+            #4 @ 3,3: 1x1
+            #5 @ 6,6: 3x1
+            #6 @ 10,10: 1x1
+        "#,
+        )
+        .unwrap();
+        assert_eq!(
+            graph.conflicts[&3],
+            vec![Conflict { id: 1, area: 1 }, Conflict { id: 4, area: 1 }]
+        );
+        assert_eq!(graph.components(), vec![vec![1, 3, 4], vec![2, 5], vec![6]]);
+        assert_eq!(graph.intact().collect::<Vec<_>>(), vec![6]);
+    }
+
+    #[test]
+    fn puzzle() {
+        let input = include_str!("../input.txt");
+        let graph = ConflictGraph::new(input).unwrap();
+        assert_eq!(graph.intact().collect::<Vec<_>>(), vec![415]);
+        // Overlapping areas are counted once per pair, so at least as often
+        // as by `overlap`.
+        let pair_area: usize = graph
+            .conflicts
+            .values()
+            .flat_map(|conflicts| conflicts.iter().map(|c| c.area))
+            .sum::<usize>()
+            / 2;
+        assert!(pair_area >= overlap(input).unwrap());
+        assert_eq!(
+            graph.components().iter().map(Vec::len).sum::<usize>(),
+            graph.conflicts.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod sweep_tests {
+    use claims;
+    use random_numbers;
+    use std::collections::HashSet;
+    use sweep::overlap_area;
+    use sweep::overlapping_pairs;
+    use used_cloth;
+    use Claim;
+
+    fn random_claims(count: usize, size: u16) -> Vec<Claim> {
+        let mut next = random_numbers();
+        (1..=count as u16)
+            .map(|id| Claim {
+                id,
+                from_left: next(size),
+                from_top: next(size),
+                width: next(size / 4),
+                height: next(size / 4),
+            })
+            .collect()
+    }
+
+    fn cell_overlap_area(claims: &[Claim]) -> usize {
+        let input = claims
+            .iter()
+            .map(|c| {
+                format!(
+                    "#{} @ {},{}: {}x{}\n",
+                    c.id, c.from_left, c.from_top, c.width, c.height
+                )
+            })
+            .collect::<String>();
+        used_cloth(&input).values().filter(|&&s| s).count()
+    }
+
+    fn cell_overlapping_pairs(claims: &[Claim]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (i, a) in claims.iter().enumerate() {
+            let cords = a.cords().collect::<HashSet<_>>();
+            for (j, b) in claims.iter().enumerate().skip(i + 1) {
+                if b.cords().any(|cord| cords.contains(&cord)) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn matches_cells() {
+        for &(count, size) in [(0, 10), (1, 10), (20, 10), (50, 40), (200, 100)].iter() {
+            let claims = random_claims(count, size);
+            assert_eq!(overlap_area(&claims), cell_overlap_area(&claims));
+            assert_eq!(overlapping_pairs(&claims), cell_overlapping_pairs(&claims));
+        }
+    }
+
+    #[test]
+    fn puzzle() {
+        let input = include_str!("../input.txt");
+        let claims = claims(input).unwrap();
+        assert_eq!(
+            overlap_area(&claims),
+            used_cloth(input).values().filter(|&&s| s).count()
+        );
+    }
+
+    #[test]
+    fn large_fabric() {
+        let claims = vec![
+            Claim {
+                id: 1,
+                from_left: 0,
+                from_top: 0,
+                width: 60000,
+                height: 60000,
+            },
+            Claim {
+                id: 2,
+                from_left: 30000,
+                from_top: 50000,
+                width: 65535,
+                height: 65535,
+            },
+        ];
+        assert_eq!(overlap_area(&claims), 30000 * 10000);
+        assert_eq!(overlapping_pairs(&claims), vec![(0, 1)]);
     }
 }
@@ -0,0 +1,205 @@
+use nom;
+use nom::types::CompleteStr;
+use serde_json;
+use std::error::Error;
+use std::fmt;
+use Claim;
+
+/// The ways claims can be written down.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    /// One claim per line, like `#1 @ 1,3: 4x4`.
+    Claims,
+    /// One claim per line, like `1,1,3,4,4`, in the order id, left, top,
+    /// width, height. There can be a header line starting with `id`.
+    Csv,
+    /// An array of objects with `id`, `left`, `top`, `width` and `height`.
+    Json,
+}
+
+impl Format {
+    /// Guesses the format from the first character that isn't whitespace.
+    pub fn detect(input: &str) -> Self {
+        match input.trim_start().chars().next() {
+            Some('[') => Format::Json,
+            Some('#') | None => Format::Claims,
+            Some(_) => Format::Csv,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    /// Counting from 1.
+    pub line: usize,
+    /// Counting characters from 1.
+    pub column: usize,
+    /// The line the error is on, without surrounding whitespace.
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    /// An error at a byte offset into the line.
+    fn new(line_number: usize, line: &str, offset: usize, message: &str) -> Self {
+        ParseError {
+            line: line_number,
+            column: line[..offset].chars().count() + 1,
+            text: line.trim().to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}: {}",
+            self.line, self.column, self.message, self.text
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// All the claims in the input, or the first thing that isn't a claim.
+pub fn parse_claims(input: &str, format: Format) -> Result<Vec<Claim>, ParseError> {
+    match format {
+        Format::Claims => claim_lines(input).collect(),
+        Format::Csv => csv_lines(input).collect(),
+        Format::Json => json(input),
+    }
+}
+
+/// Each claim in the input, or an error for each line that isn't one.
+pub(crate) fn claim_lines<'a>(
+    input: &'a str,
+) -> impl Iterator<Item = Result<Claim, ParseError>> + 'a {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| claim_line(i + 1, line))
+}
+
+named!(number<CompleteStr, u16>,
+    map_res!(nom::digit, |digits: CompleteStr| digits.0.parse())
+);
+
+named!(claim<CompleteStr, Claim>,
+    do_parse!(
+        tag!("#") >>
+        id: number >>
+        ws!(tag!("@")) >>
+        from_left: number >>
+        tag!(",") >>
+        from_top: number >>
+        ws!(tag!(":")) >>
+        width: number >>
+        tag!("x") >>
+        height: number >>
+
+        (Claim{ id, from_left, from_top, width, height})
+    )
+);
+
+fn claim_line(line_number: usize, line: &str) -> Result<Claim, ParseError> {
+    let trimmed = line.trim();
+    let start = line.len() - line.trim_start().len();
+    let error = |rest: CompleteStr, message| {
+        let offset = start + trimmed.len() - rest.0.len();
+        ParseError::new(line_number, line, offset, message)
+    };
+    match claim(CompleteStr(trimmed)) {
+        Ok((rest, claim)) => {
+            if rest.0.is_empty() {
+                Ok(claim)
+            } else {
+                Err(error(rest, "unexpected text after the claim"))
+            }
+        }
+        Err(nom::Err::Error(nom::Context::Code(rest, _)))
+        | Err(nom::Err::Failure(nom::Context::Code(rest, _))) => {
+            Err(error(rest, "expected a claim like `#1 @ 1,3: 4x4`"))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(error(
+            CompleteStr(""),
+            "expected a claim like `#1 @ 1,3: 4x4`",
+        )),
+    }
+}
+
+fn csv_lines<'a>(input: &'a str) -> impl Iterator<Item = Result<Claim, ParseError>> + 'a {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .enumerate()
+        .filter(|(row, (_, line))| {
+            let first_field = line.split(',').next().unwrap_or("").trim();
+            !(*row == 0 && first_field.eq_ignore_ascii_case("id"))
+        })
+        .map(|(_, (i, line))| csv_line(i + 1, line))
+}
+
+fn csv_line(line_number: usize, line: &str) -> Result<Claim, ParseError> {
+    let mut numbers = Vec::with_capacity(5);
+    let mut offset = 0;
+    for field in line.split(',') {
+        let field_start = offset + field.len() - field.trim_start().len();
+        offset += field.len() + 1;
+        if numbers.len() == 5 {
+            return Err(ParseError::new(
+                line_number,
+                line,
+                field_start,
+                "expected 5 fields",
+            ));
+        }
+        match field.trim().parse() {
+            Ok(number) => numbers.push(number),
+            Err(_) => {
+                return Err(ParseError::new(
+                    line_number,
+                    line,
+                    field_start,
+                    "expected a number",
+                ))
+            }
+        }
+    }
+    if numbers.len() < 5 {
+        return Err(ParseError::new(
+            line_number,
+            line,
+            line.len(),
+            "expected 5 fields",
+        ));
+    }
+    Ok(Claim {
+        id: numbers[0],
+        from_left: numbers[1],
+        from_top: numbers[2],
+        width: numbers[3],
+        height: numbers[4],
+    })
+}
+
+fn json(input: &str) -> Result<Vec<Claim>, ParseError> {
+    serde_json::from_str(input).map_err(|error| {
+        let line = error.line().max(1);
+        let message = error.to_string();
+        // The message ends with the position, which has its own fields.
+        let message = match message.rfind(" at line ") {
+            Some(position) => &message[..position],
+            None => &message[..],
+        };
+        ParseError {
+            line,
+            column: error.column().max(1),
+            text: input.lines().nth(line - 1).unwrap_or("").trim().to_string(),
+            message: message.to_string(),
+        }
+    })
+}
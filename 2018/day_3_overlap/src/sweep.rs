@@ -0,0 +1,155 @@
+use Claim;
+
+/// The area covered by two or more claims.
+pub fn overlap_area(claims: &[Claim]) -> usize {
+    covered_areas(claims).1
+}
+
+/// The areas covered by at least one claim and by two or more claims, found
+/// by sweeping a line across the fabric from left to right.
+///
+/// The line keeps track of how much of its length is covered, which only
+/// changes at the left and right edges of claims.
+pub fn covered_areas(claims: &[Claim]) -> (usize, usize) {
+    let mut edges: Vec<u32> = claims
+        .iter()
+        .flat_map(|claim| vec![claim.top(), claim.bottom()])
+        .collect();
+    edges.sort();
+    edges.dedup();
+    let index = |y: u32| edges.binary_search(&y).unwrap();
+
+    let mut events: Vec<(u32, i32, usize, usize)> = Vec::with_capacity(claims.len() * 2);
+    for claim in claims.iter().filter(|claim| !claim.is_empty()) {
+        let (top, bottom) = (index(claim.top()), index(claim.bottom()));
+        events.push((claim.left(), 1, top, bottom));
+        events.push((claim.right(), -1, top, bottom));
+    }
+    events.sort();
+
+    let mut line = CoverageTree::new(&edges);
+    let (mut once, mut twice) = (0, 0);
+    let mut previous_x = 0;
+    for (x, change, top, bottom) in events {
+        once += line.covered_once() * (x - previous_x) as usize;
+        twice += line.covered_twice() * (x - previous_x) as usize;
+        line.add(top, bottom, change);
+        previous_x = x;
+    }
+    (once, twice)
+}
+
+/// The indices of every pair of claims that overlap, with the lower index
+/// first.
+///
+/// Claims are visited by their left edge, and only compared with the claims
+/// whose right edge hasn't been passed yet.
+pub fn overlapping_pairs(claims: &[Claim]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..claims.len())
+        .filter(|&i| !claims[i].is_empty())
+        .collect();
+    order.sort_by_key(|&i| claims[i].left());
+
+    let mut pairs = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let claim = &claims[i];
+        active.retain(|&j| claims[j].right() > claim.left());
+        for &j in active.iter() {
+            let other = &claims[j];
+            if other.top() < claim.bottom() && claim.top() < other.bottom() {
+                pairs.push((i.min(j), i.max(j)));
+            }
+        }
+        active.push(i);
+    }
+    pairs.sort();
+    pairs
+}
+
+/// A segment tree over the gaps between the edges, counting how much of the
+/// sweep line is covered at least once and at least twice.
+struct CoverageTree<'a> {
+    edges: &'a [u32],
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Default)]
+struct Node {
+    /// The claims covering all of this node's gaps, and no more of its
+    /// parent's.
+    count: i32,
+    covered_once: usize,
+    covered_twice: usize,
+}
+
+impl<'a> CoverageTree<'a> {
+    fn new(edges: &'a [u32]) -> Self {
+        CoverageTree {
+            edges,
+            nodes: vec![Node::default(); 4 * edges.len().max(1)],
+        }
+    }
+
+    fn covered_once(&self) -> usize {
+        self.nodes[0].covered_once
+    }
+
+    fn covered_twice(&self) -> usize {
+        self.nodes[0].covered_twice
+    }
+
+    /// Adds `change` claims to the line between the edges with these
+    /// indices.
+    fn add(&mut self, top: usize, bottom: usize, change: i32) {
+        if self.edges.len() > 1 {
+            let gaps = self.edges.len() - 1;
+            self.update(0, 0, gaps, top, bottom, change);
+        }
+    }
+
+    fn update(
+        &mut self,
+        node: usize,
+        from: usize,
+        to: usize,
+        top: usize,
+        bottom: usize,
+        change: i32,
+    ) {
+        if bottom <= from || to <= top {
+            return;
+        }
+        if top <= from && to <= bottom {
+            self.nodes[node].count += change;
+        } else {
+            let middle = (from + to) / 2;
+            self.update(2 * node + 1, from, middle, top, bottom, change);
+            self.update(2 * node + 2, middle, to, top, bottom, change);
+        }
+        self.recalculate(node, from, to);
+    }
+
+    fn recalculate(&mut self, node: usize, from: usize, to: usize) {
+        let length = (self.edges[to] - self.edges[from]) as usize;
+        let leaf = to - from == 1;
+        let children = |tree: &Self, covered: fn(&Node) -> usize| {
+            if leaf {
+                0
+            } else {
+                covered(&tree.nodes[2 * node + 1]) + covered(&tree.nodes[2 * node + 2])
+            }
+        };
+        let (covered_once, covered_twice) = match self.nodes[node].count {
+            0 => (
+                children(self, |n| n.covered_once),
+                children(self, |n| n.covered_twice),
+            ),
+            1 => (length, children(self, |n| n.covered_once)),
+            _ => (length, length),
+        };
+        let node = &mut self.nodes[node];
+        node.covered_once = covered_once;
+        node.covered_twice = covered_twice;
+    }
+}
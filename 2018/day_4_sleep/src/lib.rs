@@ -3,54 +3,326 @@ extern crate nom;
 extern crate chrono;
 
 use chrono::naive::NaiveDate;
-use std::collections::HashMap;
+use chrono::naive::NaiveDateTime;
+use chrono::naive::NaiveTime;
+use chrono::Duration;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write;
 use std::str::FromStr;
 
 pub fn most_asleep_guard(input: &str) -> Option<u16> {
-    let nights = parse_nights(input);
-    let mut sleeping_guards = HashMap::new();
-    for night in nights.iter() {
-        *sleeping_guards.entry(night.guard_id).or_insert(0) += u16::from(night.time_asleep());
-    }
-    sleeping_guards
-        .iter()
-        .max_by_key(|(_id, &time)| time)
-        .map(|(&id, _time)| id)
+    SleepLog::new(input).most_asleep_guard()
 }
 
 pub fn most_asleep_minute(input: &str, guard_id: u16) -> Option<u8> {
-    let nights = parse_nights(input);
-    let nights_on_duty = nights.iter().filter(|night| night.guard_id == guard_id);
-    let mut minutes = HashMap::new();
-    for night in nights_on_duty {
-        for (minute, &asleep) in night.sleeps.iter().enumerate() {
-            if asleep {
-                *minutes.entry(minute as u8).or_insert(0) += 1;
-            }
+    SleepLog::new(input)
+        .most_asleep_minute(guard_id)
+        .map(|minute| minute as u8)
+}
+
+pub fn most_consistently_asleep_guard(input: &str) -> Option<u16> {
+    SleepLog::new(input).most_consistently_asleep_guard()
+}
+
+/// When during a shift the guards might be asleep. Sleep outside the window
+/// isn't counted.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ShiftWindow {
+    start: NaiveTime,
+    minutes: u32,
+}
+
+impl ShiftWindow {
+    /// Returns `None` unless the window is between a minute and a day long.
+    pub fn new(start: NaiveTime, minutes: u32) -> Option<Self> {
+        if minutes == 0 || minutes > 24 * 60 {
+            None
+        } else {
+            Some(ShiftWindow { start, minutes })
+        }
+    }
+
+    pub fn start(&self) -> NaiveTime {
+        self.start
+    }
+
+    pub fn minutes(&self) -> u32 {
+        self.minutes
+    }
+
+    /// Reads the log, making the best of anything inconsistent in it.
+    pub fn read(self, input: &str) -> SleepLog {
+        SleepLog::from_nights(self, check_nights(input, self).0)
+    }
+
+    /// Reads the log, failing with every inconsistency in it.
+    pub fn parse(self, input: &str) -> Result<SleepLog, Vec<Inconsistency>> {
+        let (nights, inconsistencies) = check_nights(input, self);
+        if inconsistencies.is_empty() {
+            Ok(SleepLog::from_nights(self, nights))
+        } else {
+            Err(inconsistencies)
         }
     }
-    minutes
-        .iter()
-        .max_by_key(|(_minute, &time)| time)
-        .map(|(&minute, _time)| minute)
+
+    /// Every inconsistency in the log, with unreadable lines first and then
+    /// the rest in the order they happened.
+    pub fn validate(self, input: &str) -> Vec<Inconsistency> {
+        check_nights(input, self).1
+    }
+
+    /// The start of the first window that ends after the shift begins.
+    fn start_for(&self, shift_begins: NaiveDateTime) -> NaiveDateTime {
+        let mut start = shift_begins.date().pred().and_time(self.start);
+        while start + Duration::minutes(i64::from(self.minutes)) <= shift_begins {
+            start += Duration::days(1);
+        }
+        start
+    }
 }
 
-pub fn most_consistently_asleep_guard(input: &str) -> Option<u16> {
-    let nights = parse_nights(input);
-    let mut guard_minutes = HashMap::new();
-    for night in nights.iter() {
-        for (minute, &asleep) in night.sleeps.iter().enumerate() {
-            if asleep {
-                *guard_minutes
-                    .entry((night.guard_id, minute as u8))
-                    .or_insert(0) += 1;
+/// The midnight hour.
+impl Default for ShiftWindow {
+    fn default() -> Self {
+        ShiftWindow {
+            start: NaiveTime::from_hms(0, 0, 0),
+            minutes: 60,
+        }
+    }
+}
+
+/// The guards' shifts, parsed once so they can be asked about repeatedly.
+#[derive(Debug)]
+pub struct SleepLog {
+    window: ShiftWindow,
+    guards: BTreeMap<u16, GuardSummary>,
+}
+
+/// Everything the log says about one guard's sleep.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GuardSummary {
+    pub guard_id: u16,
+    pub shifts: u32,
+    pub minutes_asleep: u32,
+    /// How many shifts the guard was asleep for each minute of the shift
+    /// window, counting from its start.
+    pub histogram: Vec<u32>,
+    /// The most minutes the guard slept for without waking up.
+    pub longest_nap: u32,
+    pub nights_without_sleep: u32,
+}
+
+impl GuardSummary {
+    fn new(guard_id: u16, window: ShiftWindow) -> Self {
+        GuardSummary {
+            guard_id,
+            shifts: 0,
+            minutes_asleep: 0,
+            histogram: vec![0; window.minutes as usize],
+            longest_nap: 0,
+            nights_without_sleep: 0,
+        }
+    }
+
+    /// The minute of the shift window the guard was most often asleep, and
+    /// how often. Ties go to the earliest minute.
+    pub fn sleepiest_minute(&self) -> Option<(u32, u32)> {
+        self.histogram
+            .iter()
+            .enumerate()
+            .filter(|(_minute, &count)| count > 0)
+            .fold(None, |best, (minute, &count)| match best {
+                Some((_, best_count)) if best_count >= count => best,
+                _ => Some((minute as u32, count)),
+            })
+    }
+}
+
+impl SleepLog {
+    /// Reads the log with the midnight hour as the shift window, making the
+    /// best of anything inconsistent in it. Use `parse` to refuse
+    /// inconsistent logs instead.
+    pub fn new(input: &str) -> Self {
+        ShiftWindow::default().read(input)
+    }
+
+    fn from_nights(window: ShiftWindow, nights: Vec<Night>) -> Self {
+        let mut guards = BTreeMap::new();
+        for night in nights {
+            let guard = guards
+                .entry(night.guard_id)
+                .or_insert_with(|| GuardSummary::new(night.guard_id, window));
+            guard.shifts += 1;
+            guard.minutes_asleep += night.time_asleep();
+            if night.time_asleep() == 0 {
+                guard.nights_without_sleep += 1;
+            }
+            let mut nap = 0;
+            for (minute, &asleep) in night.sleeps.iter().enumerate() {
+                if asleep {
+                    guard.histogram[minute] += 1;
+                    nap += 1;
+                    guard.longest_nap = guard.longest_nap.max(nap);
+                } else {
+                    nap = 0;
+                }
             }
         }
+        SleepLog { window, guards }
+    }
+
+    pub fn window(&self) -> ShiftWindow {
+        self.window
+    }
+
+    /// Every guard in the log, in order of ID.
+    pub fn guards(&self) -> impl Iterator<Item = &GuardSummary> {
+        self.guards.values()
+    }
+
+    pub fn guard(&self, guard_id: u16) -> Option<&GuardSummary> {
+        self.guards.get(&guard_id)
+    }
+
+    pub fn most_asleep_guard(&self) -> Option<u16> {
+        self.guards()
+            .max_by_key(|guard| guard.minutes_asleep)
+            .map(|guard| guard.guard_id)
+    }
+
+    /// The minute of the shift window the guard was most often asleep.
+    pub fn most_asleep_minute(&self, guard_id: u16) -> Option<u32> {
+        self.guard(guard_id)?
+            .sleepiest_minute()
+            .map(|(minute, _count)| minute)
+    }
+
+    pub fn most_consistently_asleep_guard(&self) -> Option<u16> {
+        self.guards()
+            .filter_map(|guard| guard.sleepiest_minute().map(|(_, count)| (guard, count)))
+            .max_by_key(|&(_guard, count)| count)
+            .map(|(guard, _count)| guard.guard_id)
+    }
+
+    /// One row per guard. The histogram has a character per minute: `.` if
+    /// the guard was never asleep then, the number of shifts if up to 9,
+    /// and `+` for more.
+    pub fn to_table(&self) -> String {
+        let mut table =
+            String::from("guard  shifts  asleep  longest nap  nights awake  histogram\n");
+        for guard in self.guards() {
+            let histogram = guard
+                .histogram
+                .iter()
+                .map(|&count| match count {
+                    0 => '.',
+                    1..=9 => (b'0' + count as u8) as char,
+                    _ => '+',
+                })
+                .collect::<String>();
+            writeln!(
+                table,
+                "{:<5}  {:>6}  {:>6}  {:>11}  {:>12}  {}",
+                format!("#{}", guard.guard_id),
+                guard.shifts,
+                guard.minutes_asleep,
+                guard.longest_nap,
+                guard.nights_without_sleep,
+                histogram
+            )
+            .unwrap();
+        }
+        table
+    }
+
+    /// A JSON array with an object per guard.
+    pub fn to_json(&self) -> String {
+        let guards = self
+            .guards()
+            .map(|guard| {
+                format!(
+                    "{{\"guard_id\":{},\"shifts\":{},\"minutes_asleep\":{},\"histogram\":[{}],\"longest_nap\":{},\"nights_without_sleep\":{}}}",
+                    guard.guard_id,
+                    guard.shifts,
+                    guard.minutes_asleep,
+                    guard
+                        .histogram
+                        .iter()
+                        .map(u32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    guard.longest_nap,
+                    guard.nights_without_sleep
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", guards.join(","))
+    }
+}
+
+impl FromStr for SleepLog {
+    type Err = Vec<Inconsistency>;
+
+    /// Reads the log with the midnight hour as the shift window, failing
+    /// with every inconsistency in it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ShiftWindow::default().parse(s)
     }
-    guard_minutes
-        .iter()
-        .max_by_key(|(_guard_minute, &time)| time)
-        .map(|(guard_minute, _time)| guard_minute.0)
+}
+
+/// Something in the log that can't be right, and would make the minutes
+/// asleep wrong.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Inconsistency {
+    /// The line of the input, counting from 1.
+    pub line: usize,
+    /// When the record says it happened, unless the line can't be read.
+    pub timestamp: Option<NaiveDateTime>,
+    pub kind: InconsistencyKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum InconsistencyKind {
+    /// The line isn't a record.
+    Unreadable(String),
+    /// A guard falls asleep or wakes up before any shift has begun.
+    BeforeFirstShift,
+    /// A guard falls asleep when they're already asleep.
+    AlreadyAsleep,
+    /// A guard wakes up when they're already awake.
+    AlreadyAwake,
+    /// A guard falls asleep and the shift ends before they wake up.
+    AsleepAtEndOfShift { guard_id: u16 },
+    /// A guard falls asleep or wakes up outside the shift window.
+    OutsideShiftWindow,
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(timestamp) = self.timestamp {
+            write!(f, " [{}]", timestamp.format("%Y-%m-%d %H:%M"))?;
+        }
+        match self.kind {
+            InconsistencyKind::Unreadable(ref text) => write!(f, ": can't read {:?}", text),
+            InconsistencyKind::BeforeFirstShift => write!(f, ": before any shift begins"),
+            InconsistencyKind::AlreadyAsleep => write!(f, ": falls asleep while asleep"),
+            InconsistencyKind::AlreadyAwake => write!(f, ": wakes up while awake"),
+            InconsistencyKind::AsleepAtEndOfShift { guard_id } => write!(
+                f,
+                ": guard #{} falls asleep and is still asleep when the shift ends",
+                guard_id
+            ),
+            InconsistencyKind::OutsideShiftWindow => write!(f, ": outside the shift window"),
+        }
+    }
+}
+
+/// Every inconsistency in the log, with the midnight hour as the shift
+/// window.
+pub fn validate(input: &str) -> Vec<Inconsistency> {
+    ShiftWindow::default().validate(input)
 }
 
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -62,113 +334,179 @@ enum Event {
 
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord)]
 struct Record {
-    date: NaiveDate,
-    hour: u8,
-    minute: u8,
+    timestamp: NaiveDateTime,
     event: Event,
+    line: usize,
 }
 
 impl Record {
-    fn night_of(&self) -> NaiveDate {
-        if self.hour == 0 {
-            self.date
-        } else {
-            self.date.succ()
+    fn inconsistency(&self, kind: InconsistencyKind) -> Inconsistency {
+        Inconsistency {
+            line: self.line,
+            timestamp: Some(self.timestamp),
+            kind,
         }
     }
 }
 
 #[derive(Debug)]
 struct Night {
-    date: NaiveDate,
+    /// When the shift window starts.
+    start: NaiveDateTime,
     guard_id: u16,
     sleeps: Vec<bool>,
 }
 
 impl Night {
-    fn time_asleep(&self) -> u8 {
-        self.sleeps.iter().filter(|&&m| m).count() as u8
+    fn time_asleep(&self) -> u32 {
+        self.sleeps.iter().filter(|&&m| m).count() as u32
+    }
+
+    /// The minutes since the start of the shift window, if the record is
+    /// within it. Waking up just as it ends counts.
+    fn minute_of(&self, record: &Record) -> Option<usize> {
+        let minute = (record.timestamp - self.start).num_minutes();
+        let last = match record.event {
+            Event::WakeUp => self.sleeps.len(),
+            _ => self.sleeps.len() - 1,
+        };
+        if 0 <= minute && minute as usize <= last {
+            Some(minute as usize)
+        } else {
+            None
+        }
     }
 }
 
-fn parse_nights(input: &str) -> Vec<Night> {
-    let mut records = parse_records(input);
+/// The nights in the log, and everything inconsistent found while reading
+/// them. Inconsistent records are skipped.
+fn check_nights(input: &str, window: ShiftWindow) -> (Vec<Night>, Vec<Inconsistency>) {
+    let (mut records, mut inconsistencies) = parse_records(input);
     records.sort_unstable();
 
     let mut nights = Vec::new();
-    let mut some_night = None;
-    let mut fall_asleep = 0;
+    let mut some_night: Option<Night> = None;
+    let mut fell_asleep: Option<Record> = None;
     for record in records.drain(..) {
         match record.event {
             Event::Begin { guard_id } => {
-                if let Some(night) = some_night {
+                if let Some(night) = some_night.take() {
+                    if let Some(asleep) = fell_asleep.take() {
+                        inconsistencies.push(asleep.inconsistency(
+                            InconsistencyKind::AsleepAtEndOfShift {
+                                guard_id: night.guard_id,
+                            },
+                        ));
+                    }
                     nights.push(night);
                 }
                 some_night = Some(Night {
-                    date: record.night_of(),
+                    start: window.start_for(record.timestamp),
                     guard_id,
-                    sleeps: (0..60).map(|_minute| false).collect(),
+                    sleeps: vec![false; window.minutes as usize],
                 });
             }
+            _ if some_night.is_none() => {
+                inconsistencies.push(record.inconsistency(InconsistencyKind::BeforeFirstShift));
+            }
+            _ if some_night.as_ref().unwrap().minute_of(&record).is_none() => {
+                inconsistencies.push(record.inconsistency(InconsistencyKind::OutsideShiftWindow));
+            }
             Event::FallAsleep => {
-                fall_asleep = record.minute;
+                if fell_asleep.is_some() {
+                    inconsistencies.push(record.inconsistency(InconsistencyKind::AlreadyAsleep));
+                } else {
+                    fell_asleep = Some(record);
+                }
             }
-            Event::WakeUp => {
-                if let Some(ref mut night) = some_night {
-                    for minute in fall_asleep..record.minute {
-                        night.sleeps[minute as usize] = true;
+            Event::WakeUp => match (some_night.as_mut(), fell_asleep.take()) {
+                (Some(night), Some(asleep)) => {
+                    let from = night.minute_of(&asleep).unwrap();
+                    let to = night.minute_of(&record).unwrap();
+                    for minute in night.sleeps[from..to].iter_mut() {
+                        *minute = true;
                     }
                 }
-            }
+                _ => {
+                    inconsistencies.push(record.inconsistency(InconsistencyKind::AlreadyAwake));
+                }
+            },
         }
     }
     if let Some(night) = some_night {
+        if let Some(asleep) = fell_asleep {
+            inconsistencies.push(asleep.inconsistency(InconsistencyKind::AsleepAtEndOfShift {
+                guard_id: night.guard_id,
+            }));
+        }
         nights.push(night);
     }
-    nights
+    (nights, inconsistencies)
 }
 
-fn parse_records(mut input: &str) -> Vec<Record> {
+/// The records in the input, and the lines that aren't records.
+fn parse_records(input: &str) -> (Vec<Record>, Vec<Inconsistency>) {
     let mut records = Vec::new();
-    loop {
-        match parse_record(input.trim_start()) {
-            Ok((remaining, record)) => {
-                input = remaining;
-                records.push(record);
-            }
-            Err(_) => return records,
+    let mut unreadable = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let text = line.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match parse_record(text) {
+            Ok(("", record)) => records.push(Record {
+                line: i + 1,
+                ..record
+            }),
+            _ => unreadable.push(Inconsistency {
+                line: i + 1,
+                timestamp: None,
+                kind: InconsistencyKind::Unreadable(text.to_string()),
+            }),
         }
     }
+    (records, unreadable)
 }
 
+named!(number<&str, u32>,
+    map_res!(nom::digit, FromStr::from_str)
+);
+
 named!(parse_record<&str, Record>,
     do_parse!(
-        tag_s!("[1518-") >>
-        month: map_res!(nom::digit, FromStr::from_str) >>
-        tag_s!("-") >>
-        day: map_res!(nom::digit, FromStr::from_str) >>
-        tag_s!(" ") >>
-        hour: map_res!(nom::digit, FromStr::from_str) >>
-        tag_s!(":") >>
-        minute: map_res!(nom::digit, FromStr::from_str) >>
-        tag_s!("] ") >>
+        tag!("[") >>
+        year: map_res!(nom::digit, FromStr::from_str) >>
+        tag!("-") >>
+        month: number >>
+        tag!("-") >>
+        day: number >>
+        tag!(" ") >>
+        hour: number >>
+        tag!(":") >>
+        minute: number >>
+        timestamp: expr_opt!(
+            NaiveDate::from_ymd_opt(year, month, day)
+                .and_then(|date| date.and_hms_opt(hour, minute, 0))
+        ) >>
+        tag!("] ") >>
         event: alt!(
             do_parse!(
-                tag_s!("Guard #") >>
+                tag!("Guard #") >>
                 guard_id: map_res!(nom::digit, FromStr::from_str) >>
-                tag_s!(" begins shift") >>
+                tag!(" begins shift") >>
                 (Event::Begin { guard_id})
             ) |
             do_parse!(
-                tag_s!("falls asleep") >>
+                tag!("falls asleep") >>
                 (Event::FallAsleep)
             ) |
             do_parse!(
-                tag_s!("wakes up") >>
+                tag!("wakes up") >>
                 (Event::WakeUp)
             )
         ) >>
-        (Record { date: NaiveDate::from_ymd(1518, month, day), hour, minute, event })
+        // The caller knows which line the record is on.
+        (Record { timestamp, event, line: 0 })
     )
 );
 
@@ -237,5 +575,259 @@ mod tests {
 
         assert_eq!(GUARD_ID as u32 * ASLEEP_MINUTE as u32, 56901);
     }
+}
+
+#[cfg(test)]
+mod sleep_log {
+    use SleepLog;
+
+    fn log() -> SleepLog {
+        SleepLog::new(include_str!("../example.txt"))
+    }
+
+    #[test]
+    fn guards() {
+        let log = log();
+        let summaries = log
+            .guards()
+            .map(|g| {
+                (
+                    g.guard_id,
+                    g.shifts,
+                    g.minutes_asleep,
+                    g.longest_nap,
+                    g.nights_without_sleep,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(summaries, vec![(10, 2, 50, 25, 0), (99, 3, 30, 10, 0)]);
+        assert_eq!(log.guard(10).unwrap().sleepiest_minute(), Some((24, 2)));
+        assert_eq!(log.guard(99).unwrap().sleepiest_minute(), Some((45, 3)));
+        assert_eq!(log.guard(99).unwrap().histogram.iter().sum::<u32>(), 30);
+        assert_eq!(log.guard(11), None);
+    }
+
+    #[test]
+    fn nights_without_sleep() {
+        let log = SleepLog::new(
+            "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-02 00:00] Guard #10 begins shift
+[1518-11-03 00:00] Guard #11 begins shift
+",
+        );
+        let guard = log.guard(10).unwrap();
+        assert_eq!((guard.shifts, guard.nights_without_sleep), (2, 1));
+        assert_eq!(log.guard(11).unwrap().sleepiest_minute(), None);
+        assert_eq!(log.most_consistently_asleep_guard(), Some(10));
+    }
+
+    #[test]
+    fn table() {
+        assert_eq!(
+            log().to_table(),
+            "guard  shifts  asleep  longest nap  nights awake  histogram
+#10         2      50           25             0  .....111111111111111111121111.1111111111111111111111111.....
+#99         3      30           10             0  ....................................1111222223222211111.....
+"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let json = log().to_json();
+        assert!(json.starts_with(
+            "[{\"guard_id\":10,\"shifts\":2,\"minutes_asleep\":50,\"histogram\":[0,0,0,0,0,1,"
+        ));
+        assert!(json.ends_with(
+            ",2,2,2,2,1,1,1,1,1,0,0,0,0,0],\"longest_nap\":10,\"nights_without_sleep\":0}]"
+        ));
+    }
+}
 
+#[cfg(test)]
+mod validation {
+    use chrono::NaiveDate;
+    use most_asleep_guard;
+    use validate;
+    use Inconsistency;
+    use InconsistencyKind::*;
+    use SleepLog;
+
+    #[test]
+    fn consistent() {
+        assert_eq!(validate(include_str!("../example.txt")), vec![]);
+        assert_eq!(validate(include_str!("../input.txt")), vec![]);
+        let log = include_str!("../example.txt").parse::<SleepLog>().unwrap();
+        assert_eq!(log.most_asleep_guard(), Some(10));
+    }
+
+    #[test]
+    fn inconsistent() {
+        let input = "[1518-10-31 23:59] wakes up
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:06] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:26] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-02 00:00] Guard #99 begins shift
+[1518-11-02 01:10] falls asleep
+[1518-02-30 00:00] Guard #99 begins shift
+the end
+";
+        let at = |line, day, hour, minute, kind| Inconsistency {
+            line,
+            timestamp: Some(NaiveDate::from_ymd(1518, 11, day).and_hms(hour, minute, 0)),
+            kind,
+        };
+        let inconsistencies = validate(input);
+        assert_eq!(
+            inconsistencies,
+            vec![
+                Inconsistency {
+                    line: 10,
+                    timestamp: None,
+                    kind: Unreadable("[1518-02-30 00:00] Guard #99 begins shift".to_string()),
+                },
+                Inconsistency {
+                    line: 11,
+                    timestamp: None,
+                    kind: Unreadable("the end".to_string()),
+                },
+                Inconsistency {
+                    line: 1,
+                    timestamp: Some(NaiveDate::from_ymd(1518, 10, 31).and_hms(23, 59, 0)),
+                    kind: BeforeFirstShift,
+                },
+                at(4, 1, 0, 6, AlreadyAsleep),
+                at(6, 1, 0, 26, AlreadyAwake),
+                at(7, 1, 0, 30, AsleepAtEndOfShift { guard_id: 10 }),
+                at(9, 2, 1, 10, OutsideShiftWindow),
+            ]
+        );
+        assert_eq!(
+            inconsistencies[6].to_string(),
+            "line 9 [1518-11-02 01:10]: outside the shift window"
+        );
+        assert_eq!(
+            inconsistencies[5].to_string(),
+            "line 7 [1518-11-01 00:30]: guard #10 falls asleep and is still asleep when the shift ends"
+        );
+        assert_eq!(
+            inconsistencies[1].to_string(),
+            "line 11: can't read \"the end\""
+        );
+        assert_eq!(input.parse::<SleepLog>().err(), Some(inconsistencies));
+
+        // Without validation, the inconsistent records are skipped.
+        let log = SleepLog::new(input);
+        assert_eq!(log.guard(10).unwrap().minutes_asleep, 20);
+        assert_eq!(log.guard(99).unwrap().minutes_asleep, 0);
+        assert_eq!(most_asleep_guard(input), Some(10));
+    }
+
+    #[test]
+    fn asleep_at_end_of_log() {
+        let input = "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+";
+        assert_eq!(
+            validate(input)
+                .into_iter()
+                .map(|i| (i.line, i.kind))
+                .collect::<Vec<_>>(),
+            vec![(2, AsleepAtEndOfShift { guard_id: 10 })]
+        );
+    }
+}
+
+#[cfg(test)]
+mod shift_window {
+    use chrono::NaiveDate;
+    use chrono::NaiveTime;
+    use Inconsistency;
+    use InconsistencyKind::*;
+    use ShiftWindow;
+
+    fn night_shift() -> ShiftWindow {
+        ShiftWindow::new(NaiveTime::from_hms(22, 0, 0), 8 * 60).unwrap()
+    }
+
+    #[test]
+    fn new() {
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+        assert_eq!(ShiftWindow::new(midnight, 60), Some(ShiftWindow::default()));
+        assert_eq!(ShiftWindow::new(midnight, 0), None);
+        assert!(ShiftWindow::new(midnight, 24 * 60).is_some());
+        assert_eq!(ShiftWindow::new(midnight, 24 * 60 + 1), None);
+    }
+
+    #[test]
+    fn night_shift_rota() {
+        let input = "[2023-12-31 21:55] Guard #7 begins shift
+[2023-12-31 23:50] falls asleep
+[2024-01-01 00:10] wakes up
+[2024-01-01 03:30] falls asleep
+[2024-01-01 04:15] wakes up
+[2024-01-01 22:30] Guard #7 begins shift
+[2024-01-02 05:50] falls asleep
+[2024-01-02 06:00] wakes up
+[2024-01-02 21:59] Guard #8 begins shift
+";
+        let log = night_shift().parse(input).unwrap();
+        let guard = log.guard(7).unwrap();
+        assert_eq!(guard.shifts, 2);
+        assert_eq!(guard.minutes_asleep, 20 + 45 + 10);
+        assert_eq!(guard.longest_nap, 45);
+        assert_eq!(guard.histogram.len(), 480);
+        assert_eq!(guard.histogram[110], 1);
+        assert_eq!(guard.histogram[479], 1);
+        assert_eq!(log.most_asleep_minute(7), Some(110));
+        assert_eq!(log.guard(8).unwrap().nights_without_sleep, 1);
+
+        // With the default window, most of that sleep is outside it.
+        assert_eq!(
+            ::validate(input)
+                .into_iter()
+                .map(|i| (i.line, i.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, OutsideShiftWindow),
+                (3, AlreadyAwake),
+                (4, OutsideShiftWindow),
+                (5, OutsideShiftWindow),
+                (7, OutsideShiftWindow),
+                (8, OutsideShiftWindow),
+            ]
+        );
+    }
+
+    #[test]
+    fn outside_window() {
+        let input = "[2024-03-01 22:00] Guard #7 begins shift
+[2024-03-02 06:00] falls asleep
+[2024-03-02 06:10] wakes up
+";
+        assert_eq!(
+            night_shift().validate(input),
+            vec![
+                Inconsistency {
+                    line: 2,
+                    timestamp: Some(NaiveDate::from_ymd(2024, 3, 2).and_hms(6, 0, 0)),
+                    kind: OutsideShiftWindow,
+                },
+                Inconsistency {
+                    line: 3,
+                    timestamp: Some(NaiveDate::from_ymd(2024, 3, 2).and_hms(6, 10, 0)),
+                    kind: OutsideShiftWindow,
+                },
+            ]
+        );
+        assert_eq!(
+            night_shift().read(input).guard(7).unwrap().minutes_asleep,
+            0
+        );
+    }
 }
@@ -0,0 +1,216 @@
+#[cfg(test)]
+use crate::{accumulative_fuel_required, fuel_required, Recursive};
+use crate::{FuelModel, SingleStage};
+use std::thread;
+
+/// `Recursive` fuel for every mass below a limit, so that long fuel chains
+/// only need to be followed until they drop below it.
+///
+/// Every chain ends in the same small masses, so their tails are shared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuelTable<M = SingleStage> {
+    stage: M,
+    tails: Vec<u64>,
+}
+
+impl<M: FuelModel> FuelTable<M> {
+    pub fn new(stage: M, limit: usize) -> Self {
+        let mut tails: Vec<u64> = Vec::with_capacity(limit);
+        for mass in 0..limit as u64 {
+            let fuel = stage.fuel_required(mass);
+            // A chain only continues while the fuel is smaller than the
+            // mass, so its tail is already in the table.
+            let tail = if fuel == 0 || fuel >= mass {
+                fuel
+            } else {
+                fuel.saturating_add(tails[fuel as usize])
+            };
+            tails.push(tail);
+        }
+        Self { stage, tails }
+    }
+
+    /// The model for a single stage.
+    pub fn stage(&self) -> &M {
+        &self.stage
+    }
+
+    /// The same as `Recursive::new(stage).fuel_required`.
+    pub fn accumulative_fuel_required(&self, mut mass: u64) -> u64 {
+        let mut sum = 0u64;
+        loop {
+            if let Some(&tail) = self.tails.get(mass as usize) {
+                return sum.saturating_add(tail);
+            }
+            let fuel = self.stage.fuel_required(mass);
+            sum = sum.saturating_add(fuel);
+            if fuel == 0 || fuel >= mass {
+                return sum;
+            }
+            mass = fuel;
+        }
+    }
+}
+
+impl Default for FuelTable {
+    fn default() -> Self {
+        Self::new(SingleStage::default(), 1 << 16)
+    }
+}
+
+/// Uses the table as the `Recursive` model of its stage.
+impl<M: FuelModel> FuelModel for FuelTable<M> {
+    fn fuel_required(&self, mass: u64) -> u64 {
+        self.accumulative_fuel_required(mass)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Totals {
+    pub fuel: u128,
+    pub accumulative_fuel: u128,
+}
+
+impl Totals {
+    fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Self {
+            fuel: self.fuel.checked_add(other.fuel)?,
+            accumulative_fuel: self
+                .accumulative_fuel
+                .checked_add(other.accumulative_fuel)?,
+        })
+    }
+}
+
+/// The fuel under the table's stage model, and the accumulative fuel under
+/// its `Recursive` model, for all the masses. Returns `None` if either total
+/// overflows.
+pub fn totals<M: FuelModel>(masses: &[u64], table: &FuelTable<M>) -> Option<Totals> {
+    masses.iter().try_fold(Totals::default(), |totals, &mass| {
+        totals.checked_add(Totals {
+            fuel: table.stage.fuel_required(mass).into(),
+            accumulative_fuel: table.accumulative_fuel_required(mass).into(),
+        })
+    })
+}
+
+/// The same as `totals`, but splits the masses into chunks that are summed
+/// on separate threads.
+pub fn parallel_totals<M: FuelModel + Sync>(
+    masses: &[u64],
+    table: &FuelTable<M>,
+    threads: usize,
+) -> Option<Totals> {
+    let threads = threads.max(1);
+    let chunk_size = masses.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles = masses
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || totals(chunk, table)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("fuel thread panicked"))
+            .try_fold(Totals::default(), |totals, chunk| {
+                totals.checked_add(chunk?)
+            })
+    })
+}
+
+#[cfg(test)]
+fn random_masses(count: usize) -> Vec<u64> {
+    // xorshift, so the test doesn't need a dependency.
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    (0..count)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            // Mix in small masses, which only use the table.
+            match i % 3 {
+                0 => state % 100_000,
+                _ => state,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_fuel_table() {
+    let table = FuelTable::default();
+    let small_table = FuelTable::new(SingleStage::default(), 10);
+    for mass in random_masses(10_000).into_iter().chain(vec![
+        0,
+        1,
+        14,
+        1969,
+        100756,
+        u64::MAX - 1,
+        u64::MAX,
+    ]) {
+        assert_eq!(
+            table.accumulative_fuel_required(mass),
+            accumulative_fuel_required(mass)
+        );
+        assert_eq!(
+            small_table.accumulative_fuel_required(mass),
+            accumulative_fuel_required(mass)
+        );
+    }
+}
+
+#[test]
+fn test_fuel_table_models() {
+    let halving = SingleStage::new(2, 0).unwrap();
+    let identity = SingleStage::new(1, 0).unwrap();
+    let slow = SingleStage::new(7, 1).unwrap();
+    for &stage in [halving, identity, slow].iter() {
+        let recursive = Recursive::new(stage);
+        for &limit in [0, 1, 10, 1000].iter() {
+            let table = FuelTable::new(stage, limit);
+            for mass in random_masses(1000).into_iter().chain(vec![0, 1, 2, 100]) {
+                assert_eq!(table.fuel_required(mass), recursive.fuel_required(mass));
+            }
+        }
+    }
+
+    let masses = random_masses(1000);
+    let table = FuelTable::new(halving, 1000);
+    let expected = Totals {
+        fuel: masses
+            .iter()
+            .map(|&m| u128::from(halving.fuel_required(m)))
+            .sum(),
+        accumulative_fuel: masses
+            .iter()
+            .map(|&m| u128::from(Recursive::new(halving).fuel_required(m)))
+            .sum(),
+    };
+    assert_eq!(totals(&masses, &table), Some(expected));
+    assert_eq!(parallel_totals(&masses, &table, 4), Some(expected));
+}
+
+#[test]
+fn test_totals() {
+    let masses = random_masses(100_000);
+    let expected = Totals {
+        fuel: masses.iter().map(|&m| u128::from(fuel_required(m))).sum(),
+        accumulative_fuel: masses
+            .iter()
+            .map(|&m| u128::from(accumulative_fuel_required(m)))
+            .sum(),
+    };
+    let table = FuelTable::default();
+    assert_eq!(totals(&masses, &table), Some(expected));
+    for &threads in [0, 1, 3, 8].iter() {
+        assert_eq!(parallel_totals(&masses, &table, threads), Some(expected));
+    }
+    assert_eq!(parallel_totals(&[], &table, 4), Some(Totals::default()));
+
+    let max = Totals {
+        fuel: u128::MAX,
+        accumulative_fuel: 0,
+    };
+    assert_eq!(max.checked_add(Totals::default()), Some(max));
+    assert_eq!(max.checked_add(max), None);
+}
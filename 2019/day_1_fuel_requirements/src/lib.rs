@@ -0,0 +1,259 @@
+pub mod batch;
+
+use std::num::ParseIntError;
+
+pub fn fuel_required(mass: u64) -> u64 {
+    (mass / 3).saturating_sub(2)
+}
+
+#[test]
+fn test_fuel_required() {
+    assert_eq!(fuel_required(12), 2);
+    assert_eq!(fuel_required(14), 2);
+    assert_eq!(fuel_required(1969), 654);
+    assert_eq!(fuel_required(100756), 33583);
+}
+
+pub fn accumulative_fuel_required(mut mass: u64) -> u64 {
+    let mut sum = 0;
+    while mass != 0 {
+        mass = fuel_required(mass);
+        sum += mass;
+    }
+    sum
+}
+
+#[test]
+fn test_accumulative_fuel_required() {
+    assert_eq!(accumulative_fuel_required(14), 2);
+    assert_eq!(accumulative_fuel_required(1969), 966);
+    assert_eq!(accumulative_fuel_required(100756), 50346);
+}
+
+pub trait FuelModel {
+    /// The fuel needed to launch a module of the given mass.
+    fn fuel_required(&self, mass: u64) -> u64;
+
+    fn breakdown(&self, modules: &[Module]) -> Breakdown {
+        Breakdown {
+            modules: modules
+                .iter()
+                .map(|&module| ModuleFuel {
+                    module,
+                    fuel: self.fuel_required(module.mass),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Fuel for the module's mass alone: the mass divided by the divisor
+/// (rounding down), minus the subtraction, and never less than zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SingleStage {
+    divisor: u64,
+    subtraction: u64,
+}
+
+impl SingleStage {
+    /// Returns `None` if the divisor is zero.
+    pub fn new(divisor: u64, subtraction: u64) -> Option<Self> {
+        if divisor == 0 {
+            None
+        } else {
+            Some(Self {
+                divisor,
+                subtraction,
+            })
+        }
+    }
+}
+
+impl Default for SingleStage {
+    fn default() -> Self {
+        Self {
+            divisor: 3,
+            subtraction: 2,
+        }
+    }
+}
+
+impl FuelModel for SingleStage {
+    fn fuel_required(&self, mass: u64) -> u64 {
+        (mass / self.divisor).saturating_sub(self.subtraction)
+    }
+}
+
+/// Fuel for the module's mass, plus fuel for that fuel, and so on until a
+/// stage needs no more fuel.
+///
+/// A stage needing at least as much fuel as the mass it launches would
+/// never finish, so that stage's fuel is the last to be counted.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Recursive<M = SingleStage> {
+    stage: M,
+}
+
+impl<M: FuelModel> Recursive<M> {
+    pub fn new(stage: M) -> Self {
+        Self { stage }
+    }
+}
+
+impl<M: FuelModel> FuelModel for Recursive<M> {
+    fn fuel_required(&self, mut mass: u64) -> u64 {
+        let mut sum = 0u64;
+        loop {
+            let fuel = self.stage.fuel_required(mass);
+            sum = sum.saturating_add(fuel);
+            if fuel == 0 || fuel >= mass {
+                return sum;
+            }
+            mass = fuel;
+        }
+    }
+}
+
+#[test]
+fn test_models() {
+    for &mass in [0, 1, 12, 14, 1969, 100756, 126360].iter() {
+        assert_eq!(
+            SingleStage::default().fuel_required(mass),
+            fuel_required(mass)
+        );
+        assert_eq!(
+            Recursive::<SingleStage>::default().fuel_required(mass),
+            accumulative_fuel_required(mass)
+        );
+    }
+    assert_eq!(SingleStage::new(0, 2), None);
+    let halving = SingleStage::new(2, 0).unwrap();
+    assert_eq!(halving.fuel_required(100), 50);
+    assert_eq!(
+        Recursive::new(halving).fuel_required(100),
+        50 + 25 + 12 + 6 + 3 + 1
+    );
+    // Needs as much fuel as it has mass, forever.
+    let identity = SingleStage::new(1, 0).unwrap();
+    assert_eq!(Recursive::new(identity).fuel_required(100), 100);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Module {
+    /// The line of the manifest the module was read from, counting from 1.
+    pub line: usize,
+    pub mass: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseFailure {
+    pub line: usize,
+    pub text: String,
+    pub error: ParseIntError,
+}
+
+/// A list of module masses, one per line.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Manifest {
+    pub modules: Vec<Module>,
+    /// Lines that aren't a mass. Blank lines are skipped.
+    pub failures: Vec<ParseFailure>,
+}
+
+impl<'a> From<&'a str> for Manifest {
+    fn from(input: &'a str) -> Self {
+        let mut manifest = Manifest::default();
+        for (i, text) in input.lines().enumerate() {
+            let line = i + 1;
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            match text.parse() {
+                Ok(mass) => manifest.modules.push(Module { line, mass }),
+                Err(error) => manifest.failures.push(ParseFailure {
+                    line,
+                    text: text.to_string(),
+                    error,
+                }),
+            }
+        }
+        manifest
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModuleFuel {
+    pub module: Module,
+    pub fuel: u64,
+}
+
+/// The fuel needed by each module under one fuel model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakdown {
+    pub modules: Vec<ModuleFuel>,
+}
+
+impl Breakdown {
+    /// The total fuel, which can be more than any one module's fuel.
+    pub fn total(&self) -> u128 {
+        self.modules.iter().map(|m| u128::from(m.fuel)).sum()
+    }
+}
+
+#[test]
+fn test_manifest() {
+    let manifest = Manifest::from("12\n\n1969\nabc\n-4\n100756\n");
+    assert_eq!(
+        manifest.modules,
+        vec!(
+            Module { line: 1, mass: 12 },
+            Module {
+                line: 3,
+                mass: 1969
+            },
+            Module {
+                line: 6,
+                mass: 100756
+            }
+        )
+    );
+    assert_eq!(
+        manifest
+            .failures
+            .iter()
+            .map(|f| (f.line, &f.text[..]))
+            .collect::<Vec<_>>(),
+        vec!((4, "abc"), (5, "-4"))
+    );
+
+    let breakdown = Recursive::<SingleStage>::default().breakdown(&manifest.modules);
+    assert_eq!(
+        breakdown.modules.iter().map(|m| m.fuel).collect::<Vec<_>>(),
+        vec!(2, 966, 50346)
+    );
+    assert_eq!(breakdown.total(), 2 + 966 + 50346);
+
+    let heavy = Manifest::from(format!("{}\n{}\n", u64::MAX, u64::MAX).as_str());
+    let identity = SingleStage::new(1, 0).unwrap();
+    assert_eq!(
+        identity.breakdown(&heavy.modules).total(),
+        2 * u128::from(u64::MAX)
+    );
+}
+
+#[test]
+fn test_puzzle() {
+    let manifest = Manifest::from(include_str!("../input"));
+    assert!(manifest.failures.is_empty());
+    assert_eq!(
+        SingleStage::default().breakdown(&manifest.modules).total(),
+        3254441
+    );
+    assert_eq!(
+        Recursive::<SingleStage>::default()
+            .breakdown(&manifest.modules)
+            .total(),
+        4878818
+    );
+}
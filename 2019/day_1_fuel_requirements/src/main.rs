@@ -1,37 +1,49 @@
-fn fuel_required(mass: u64) -> u64 {
-    (mass / 3).saturating_sub(2)
-}
-
-#[test]
-fn test_fuel_required() {
-    assert_eq!(fuel_required(12), 2);
-    assert_eq!(fuel_required(14), 2);
-    assert_eq!(fuel_required(1969), 654);
-    assert_eq!(fuel_required(100756), 33583);
-}
+use day_1_fuel_requirements::batch::{self, FuelTable};
+use day_1_fuel_requirements::{FuelModel, Manifest, SingleStage};
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::thread;
 
-fn accumulative_fuel_required(mut mass: u64) -> u64 {
-    let mut sum = 0;
-    while mass != 0 {
-        mass = fuel_required(mass);
-        sum += mass;
+/// Reads module masses from the file given as an argument, or from stdin if
+/// there isn't one. Pass `--breakdown` to list the fuel for every module.
+fn main() -> io::Result<()> {
+    let (flags, paths): (Vec<_>, Vec<_>) = env::args().skip(1).partition(|a| a.starts_with("--"));
+    let show_breakdown = flags.iter().any(|flag| flag == "--breakdown");
+    let input = match paths.first() {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
+    let manifest = Manifest::from(&input[..]);
+    let table = FuelTable::new(SingleStage::default(), 1 << 16);
+    if show_breakdown {
+        let fuel = table.stage().breakdown(&manifest.modules);
+        let accumulative_fuel = table.breakdown(&manifest.modules);
+        for (module, accumulative) in fuel.modules.iter().zip(accumulative_fuel.modules.iter()) {
+            println!(
+                "line {}: mass {}, fuel {}, accumulative fuel {}",
+                module.module.line, module.module.mass, module.fuel, accumulative.fuel
+            );
+        }
     }
-    sum
-}
-
-#[test]
-fn test_accumulative_fuel_required() {
-    assert_eq!(accumulative_fuel_required(14), 2);
-    assert_eq!(accumulative_fuel_required(1969), 966);
-    assert_eq!(accumulative_fuel_required(100756), 50346);
-}
-
-fn main() {
-    let input = include_str!("../input");
-    let sum = input
-        .lines()
-        .map(|line| line.parse::<u64>())
-        .map(|rmass| rmass.map(accumulative_fuel_required))
-        .sum::<Result<u64, _>>();
-    println!("{:?}", sum);
+    let masses = manifest.modules.iter().map(|m| m.mass).collect::<Vec<_>>();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    match batch::parallel_totals(&masses, &table, threads) {
+        Some(totals) => {
+            println!("fuel: {}", totals.fuel);
+            println!("accumulative fuel: {}", totals.accumulative_fuel);
+        }
+        None => eprintln!("the fuel totals overflowed"),
+    }
+    for failure in manifest.failures.iter() {
+        eprintln!(
+            "line {}: {:?} is not a mass: {}",
+            failure.line, failure.text, failure.error
+        );
+    }
+    Ok(())
 }
@@ -0,0 +1,868 @@
+pub mod symbolic;
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// A value that can be stored in an `IntComputer`'s memory.
+///
+/// Arithmetic is checked, so overflow is a fault rather than a wrapped or
+/// panicking result.
+pub trait Word:
+    Copy
+    + PartialEq
+    + PartialOrd
+    + fmt::Debug
+    + fmt::Display
+    + FromStr<Err = ParseIntError>
+    + From<u8>
+    + Into<i128>
+{
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_word!(i32, i64, u32, u64);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operation {
+    Add,
+    Multiply,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustRelativeBase,
+    Halt,
+}
+
+impl Operation {
+    fn from_opcode(opcode: usize) -> Option<Self> {
+        use Operation::*;
+        Some(match opcode {
+            1 => Add,
+            2 => Multiply,
+            3 => Input,
+            4 => Output,
+            5 => JumpIfTrue,
+            6 => JumpIfFalse,
+            7 => LessThan,
+            8 => Equals,
+            9 => AdjustRelativeBase,
+            99 => Halt,
+            _ => return None,
+        })
+    }
+
+    /// How many parameters follow the opcode in memory.
+    fn arity(self) -> usize {
+        use Operation::*;
+        match self {
+            Add | Multiply | LessThan | Equals => 3,
+            JumpIfTrue | JumpIfFalse => 2,
+            Input | Output | AdjustRelativeBase => 1,
+            Halt => 0,
+        }
+    }
+
+    /// Which parameter (counting from 1), if any, is written to.
+    fn write_parameter(self) -> Option<usize> {
+        use Operation::*;
+        match self {
+            Add | Multiply | LessThan | Equals => Some(3),
+            Input => Some(1),
+            Output | JumpIfTrue | JumpIfFalse | AdjustRelativeBase | Halt => None,
+        }
+    }
+
+    fn mnemonic(self) -> &'static str {
+        use Operation::*;
+        match self {
+            Add => "ADD",
+            Multiply => "MUL",
+            Input => "IN",
+            Output => "OUT",
+            JumpIfTrue => "JT",
+            JumpIfFalse => "JF",
+            LessThan => "LT",
+            Equals => "EQ",
+            AdjustRelativeBase => "ARB",
+            Halt => "HALT",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParameterMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl ParameterMode {
+    fn from_digit(digit: usize) -> Option<Self> {
+        match digit {
+            0 => Some(ParameterMode::Position),
+            1 => Some(ParameterMode::Immediate),
+            2 => Some(ParameterMode::Relative),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Instruction {
+    operation: Operation,
+    modes: [ParameterMode; 3],
+}
+
+impl Instruction {
+    fn decode(value: usize) -> Option<Self> {
+        let operation = Operation::from_opcode(value % 100)?;
+        let mut modes = [ParameterMode::Position; 3];
+        let mut digits = value / 100;
+        for mode in modes.iter_mut() {
+            *mode = ParameterMode::from_digit(digits % 10)?;
+            digits /= 10;
+        }
+        Some(Self { operation, modes })
+    }
+
+    /// Renders the instruction in the form `ADD [4] 5 -> [rb+0]`, given the
+    /// raw parameter values that follow it in memory.
+    fn render<W: Word>(&self, parameters: &[W]) -> String {
+        let mut text = self.operation.mnemonic().to_string();
+        for (p, (value, mode)) in parameters.iter().zip(self.modes.iter()).enumerate() {
+            text.push(' ');
+            if self.operation.write_parameter() == Some(p + 1) {
+                text.push_str("-> ");
+            }
+            text.push_str(&match mode {
+                ParameterMode::Position => format!("[{}]", value),
+                ParameterMode::Immediate => format!("{}", value),
+                ParameterMode::Relative if (*value).into() < 0 => format!("[rb{}]", value),
+                ParameterMode::Relative => format!("[rb+{}]", value),
+            });
+        }
+        text
+    }
+}
+
+/// Renders memory as one annotated instruction per line.
+///
+/// Values that don't decode to an instruction are rendered as `DATA`.
+pub fn disassemble<W: Word>(memory: &[W]) -> String {
+    let mut lines = Vec::new();
+    let mut address = 0;
+    while let Some(&value) = memory.get(address) {
+        let instruction = usize::try_from(value.into())
+            .ok()
+            .and_then(Instruction::decode);
+        let parameters =
+            instruction.and_then(|i| memory.get(address + 1..address + 1 + i.operation.arity()));
+        match (instruction, parameters) {
+            (Some(instruction), Some(parameters)) => {
+                lines.push(format!("{}: {}", address, instruction.render(parameters)));
+                address += 1 + parameters.len();
+            }
+            _ => {
+                lines.push(format!("{}: DATA {}", address, value));
+                address += 1;
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// A record of one executed instruction.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TraceEntry<W = i64> {
+    pub instruction_pointer: usize,
+    pub instruction: String,
+    /// The values of the parameters that were read, in the order they were
+    /// read.
+    pub operands: Vec<W>,
+    /// The address written to and the value written.
+    pub write: Option<(usize, W)>,
+}
+
+impl<W: Word> fmt::Display for TraceEntry<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.instruction_pointer, self.instruction)?;
+        if !self.operands.is_empty() {
+            let operands = self
+                .operands
+                .iter()
+                .map(|operand| operand.to_string())
+                .collect::<Vec<_>>();
+            write!(f, " ; reads {}", operands.join(", "))?;
+        }
+        if let Some((address, value)) = self.write {
+            write!(f, " ; [{}] = {}", address, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IntComputer<W = i64> {
+    instruction_pointer: usize,
+    relative_base: i128,
+    memory: Vec<W>,
+    input: VecDeque<W>,
+    output: Vec<W>,
+    trace: Option<Vec<TraceEntry<W>>>,
+    /// The entry for the instruction being executed, while tracing.
+    current: Option<TraceEntry<W>>,
+}
+
+impl<W: Word> IntComputer<W> {
+    pub fn new(memory: Vec<W>) -> Self {
+        Self {
+            memory,
+            instruction_pointer: 0,
+            relative_base: 0,
+            input: VecDeque::new(),
+            output: Vec::new(),
+            trace: None,
+            current: None,
+        }
+    }
+
+    /// Renders the current memory as annotated instructions.
+    pub fn disassemble(&self) -> String {
+        disassemble(&self.memory)
+    }
+
+    /// Starts recording every executed instruction.
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    /// The instructions executed since tracing was enabled.
+    pub fn trace(&self) -> &[TraceEntry<W>] {
+        self.trace.as_ref().map_or(&[], |trace| &trace[..])
+    }
+
+    pub fn memory(&self) -> &[W] {
+        &self.memory
+    }
+
+    pub fn output(&self) -> &[W] {
+        &self.output
+    }
+
+    /// Queues a value for the program's next input instruction.
+    pub fn push_input(&mut self, value: W) {
+        self.input.push_back(value);
+    }
+
+    pub fn peek(&self, address: usize) -> Result<W, Fault> {
+        self.load(address)
+    }
+
+    pub fn poke(&mut self, address: usize, value: W) -> Result<(), Fault> {
+        match self.memory.get_mut(address) {
+            Some(cell) => {
+                *cell = value;
+                Ok(())
+            }
+            None => Err(self.fault(FaultKind::AddressOutOfBounds(address))),
+        }
+    }
+
+    fn fault(&self, kind: FaultKind) -> Fault {
+        Fault {
+            instruction_pointer: self.instruction_pointer,
+            kind,
+        }
+    }
+
+    fn load(&self, address: usize) -> Result<W, Fault> {
+        self.memory
+            .get(address)
+            .cloned()
+            .ok_or_else(|| self.fault(FaultKind::AddressOutOfBounds(address)))
+    }
+
+    fn to_address(&self, value: i128) -> Result<usize, Fault> {
+        usize::try_from(value).map_err(|_| self.fault(FaultKind::InvalidAddress(value)))
+    }
+
+    fn overflow(&self) -> Fault {
+        self.fault(FaultKind::Overflow)
+    }
+
+    /// The address the nth parameter (counting from 1) of the instruction
+    /// refers to.
+    fn address(&self, instruction: &Instruction, parameter: usize) -> Result<usize, Fault> {
+        let address = self.instruction_pointer + parameter;
+        match instruction.modes[parameter - 1] {
+            ParameterMode::Position => self.to_address(self.load(address)?.into()),
+            ParameterMode::Immediate => Ok(address),
+            ParameterMode::Relative => self.to_address(
+                self.relative_base
+                    .checked_add(self.load(address)?.into())
+                    .ok_or_else(|| self.overflow())?,
+            ),
+        }
+    }
+
+    fn read(&mut self, instruction: &Instruction, parameter: usize) -> Result<W, Fault> {
+        let value = self.load(self.address(instruction, parameter)?)?;
+        if let Some(entry) = self.current.as_mut() {
+            entry.operands.push(value);
+        }
+        Ok(value)
+    }
+
+    fn write(
+        &mut self,
+        instruction: &Instruction,
+        parameter: usize,
+        value: W,
+    ) -> Result<(), Fault> {
+        if instruction.modes[parameter - 1] == ParameterMode::Immediate {
+            return Err(self.fault(FaultKind::ImmediateWrite));
+        }
+        let address = self.address(instruction, parameter)?;
+        self.poke(address, value)?;
+        if let Some(entry) = self.current.as_mut() {
+            entry.write = Some((address, value));
+        }
+        Ok(())
+    }
+
+    /// Executes a single instruction. Returns false once the program has
+    /// halted.
+    ///
+    /// Output is collected rather than yielded, and running out of input is
+    /// a fault.
+    pub fn step(&mut self) -> Result<bool, Fault> {
+        match self.execute()? {
+            Some(Event::Halted) => Ok(false),
+            Some(Event::NeedsInput) => Err(self.fault(FaultKind::InputExhausted)),
+            Some(Event::Output(value)) => {
+                self.output.push(value);
+                Ok(true)
+            }
+            None => Ok(true),
+        }
+    }
+
+    /// Runs until the program halts, waits for input, or produces output.
+    ///
+    /// When input is needed the instruction pointer stays on the input
+    /// instruction, so feeding input and calling this again resumes the
+    /// program.
+    pub fn run_until_event(&mut self) -> Result<Event<W>, Fault> {
+        loop {
+            if let Some(event) = self.execute()? {
+                return Ok(event);
+            }
+        }
+    }
+
+    fn execute(&mut self) -> Result<Option<Event<W>>, Fault> {
+        if self.trace.is_some() {
+            self.current = self.decode().ok().map(|i| self.trace_entry(&i));
+        }
+        let result = self.execute_instruction();
+        if let (Some(entry), Some(trace)) = (self.current.take(), self.trace.as_mut()) {
+            match result {
+                Ok(Some(Event::NeedsInput)) | Err(_) => {}
+                Ok(_) => trace.push(entry),
+            }
+        }
+        result
+    }
+
+    /// Describes the instruction about to be executed. The operands and the
+    /// write are recorded as the instruction reads and writes them.
+    fn trace_entry(&self, instruction: &Instruction) -> TraceEntry<W> {
+        let start = self.instruction_pointer + 1;
+        let end = (start + instruction.operation.arity()).min(self.memory.len());
+        TraceEntry {
+            instruction_pointer: self.instruction_pointer,
+            // Parameters past the end of memory fault when they're used.
+            instruction: instruction.render(self.memory.get(start..end).unwrap_or(&[])),
+            operands: Vec::new(),
+            write: None,
+        }
+    }
+
+    fn decode(&self) -> Result<Instruction, Fault> {
+        let opcode = self.load(self.instruction_pointer)?;
+        usize::try_from(opcode.into())
+            .ok()
+            .and_then(Instruction::decode)
+            .ok_or_else(|| self.fault(FaultKind::UnknownOpcode(opcode.into())))
+    }
+
+    fn execute_instruction(&mut self) -> Result<Option<Event<W>>, Fault> {
+        use Operation::*;
+        let instruction = self.decode()?;
+        let i = &instruction;
+        let mut next_instruction = self.instruction_pointer + 1 + i.operation.arity();
+        let mut event = None;
+        match i.operation {
+            Add => {
+                let sum = W::checked_add(self.read(i, 1)?, self.read(i, 2)?)
+                    .ok_or_else(|| self.overflow())?;
+                self.write(i, 3, sum)?
+            }
+            Multiply => {
+                let product = W::checked_mul(self.read(i, 1)?, self.read(i, 2)?)
+                    .ok_or_else(|| self.overflow())?;
+                self.write(i, 3, product)?
+            }
+            Input => match self.input.pop_front() {
+                Some(value) => self.write(i, 1, value)?,
+                None => return Ok(Some(Event::NeedsInput)),
+            },
+            Output => event = Some(Event::Output(self.read(i, 1)?)),
+            JumpIfTrue => {
+                if self.read(i, 1)? != W::from(0) {
+                    let target = self.read(i, 2)?;
+                    next_instruction = self.to_address(target.into())?;
+                }
+            }
+            JumpIfFalse => {
+                if self.read(i, 1)? == W::from(0) {
+                    let target = self.read(i, 2)?;
+                    next_instruction = self.to_address(target.into())?;
+                }
+            }
+            LessThan => {
+                let less = self.read(i, 1)? < self.read(i, 2)?;
+                self.write(i, 3, W::from(less as u8))?
+            }
+            Equals => {
+                let equal = self.read(i, 1)? == self.read(i, 2)?;
+                self.write(i, 3, W::from(equal as u8))?
+            }
+            AdjustRelativeBase => {
+                self.relative_base = self
+                    .relative_base
+                    .checked_add(self.read(i, 1)?.into())
+                    .ok_or_else(|| self.overflow())?
+            }
+            Halt => return Ok(Some(Event::Halted)),
+        };
+        self.instruction_pointer = next_instruction;
+        Ok(event)
+    }
+
+    /// Runs until the program halts.
+    pub fn run(&mut self) -> Result<(), Fault> {
+        while self.step()? {}
+        Ok(())
+    }
+
+    /// Runs until the program halts and returns the final memory.
+    pub fn evaluate(mut self) -> Result<Vec<W>, Fault> {
+        self.run()?;
+        Ok(self.memory)
+    }
+}
+
+impl<W: Word> FromStr for IntComputer<W> {
+    type Err = ParseIntError;
+
+    /// Reads a comma separated program.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<Result<_, _>>()
+            .map(Self::new)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Event<W = i64> {
+    Halted,
+    NeedsInput,
+    Output(W),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Fault {
+    pub instruction_pointer: usize,
+    pub kind: FaultKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FaultKind {
+    UnknownOpcode(i128),
+    AddressOutOfBounds(usize),
+    /// The value can't be an address, such as a negative number.
+    InvalidAddress(i128),
+    ImmediateWrite,
+    InputExhausted,
+    Overflow,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at instruction {}: ", self.instruction_pointer)?;
+        match self.kind {
+            FaultKind::UnknownOpcode(opcode) => write!(f, "unexpected opcode {}", opcode),
+            FaultKind::AddressOutOfBounds(address) => {
+                write!(f, "address {} is out of bounds", address)
+            }
+            FaultKind::InvalidAddress(value) => write!(f, "{} is not a valid address", value),
+            FaultKind::ImmediateWrite => write!(f, "cannot write to an immediate parameter"),
+            FaultKind::InputExhausted => write!(f, "input exhausted"),
+            FaultKind::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for Fault {}
+
+pub fn evaluate<W: Word>(memory: Vec<W>) -> Result<Vec<W>, Fault> {
+    IntComputer::new(memory).evaluate()
+}
+
+#[test]
+fn test_evaluate() {
+    assert_eq!(evaluate(vec!(1, 0, 0, 0, 99)), Ok(vec!(2, 0, 0, 0, 99)));
+    assert_eq!(evaluate(vec!(2, 3, 0, 3, 99)), Ok(vec!(2, 3, 0, 6, 99)));
+    assert_eq!(
+        evaluate(vec!(2, 4, 4, 5, 99, 0)),
+        Ok(vec!(2, 4, 4, 5, 99, 9801))
+    );
+    assert_eq!(
+        evaluate(vec!(1, 1, 1, 4, 99, 5, 6, 0, 99)),
+        Ok(vec!(30, 1, 1, 4, 2, 5, 6, 0, 99))
+    );
+}
+
+#[test]
+fn test_parameter_modes() {
+    assert_eq!(
+        evaluate(vec!(1002, 4, 3, 4, 33)),
+        Ok(vec!(1002, 4, 3, 4, 99))
+    );
+    assert_eq!(
+        evaluate(vec!(109, 2, 21101, 3, 4, 1, 99)),
+        Ok(vec!(109, 2, 21101, 7, 4, 1, 99))
+    );
+}
+
+#[test]
+fn test_input_output() {
+    let run = |program: &[u32], input: u32| {
+        let mut computer = IntComputer::new(program.to_vec());
+        computer.push_input(input);
+        computer.run().unwrap();
+        computer.output
+    };
+    assert_eq!(run(&[3, 0, 4, 0, 99], 42), vec!(42));
+
+    // Outputs whether the input is equal to 8, less than 8, or jumps over
+    // the output when it is zero.
+    let equal_to_8 = [3, 9, 8, 9, 10, 9, 4, 9, 99, 0, 8];
+    assert_eq!(run(&equal_to_8, 8), vec!(1));
+    assert_eq!(run(&equal_to_8, 7), vec!(0));
+    let less_than_8 = [3, 3, 1107, 0, 8, 3, 4, 3, 99];
+    assert_eq!(run(&less_than_8, 7), vec!(1));
+    assert_eq!(run(&less_than_8, 9), vec!(0));
+    let non_zero = [3, 3, 1105, 0, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+    assert_eq!(run(&non_zero, 0), vec!(0));
+    assert_eq!(run(&non_zero, 5), vec!(1));
+}
+
+#[test]
+fn test_faults() {
+    let fault = |instruction_pointer, kind| {
+        Err(Fault {
+            instruction_pointer,
+            kind,
+        })
+    };
+    assert_eq!(
+        evaluate(vec!(1, 0, 0, 0, 42)),
+        fault(4, FaultKind::UnknownOpcode(42))
+    );
+    assert_eq!(
+        evaluate(vec!(1, 0, 7, 0, 99)),
+        fault(0, FaultKind::AddressOutOfBounds(7))
+    );
+    assert_eq!(
+        evaluate(vec!(1, 0, 0, 9, 99)),
+        fault(0, FaultKind::AddressOutOfBounds(9))
+    );
+    assert_eq!(
+        evaluate(vec!(1, 0, 0)),
+        fault(0, FaultKind::AddressOutOfBounds(3))
+    );
+    assert_eq!(
+        evaluate(vec!(11101, 0, 0, 0, 99)),
+        fault(0, FaultKind::ImmediateWrite)
+    );
+    assert_eq!(
+        evaluate(vec!(3, 0, 99)),
+        fault(0, FaultKind::InputExhausted)
+    );
+}
+
+#[test]
+fn test_from_str() {
+    let computer: IntComputer = "1,9,10,3,\n2,3,11,0,99,30,40,50\n".parse().unwrap();
+    assert_eq!(
+        computer.memory(),
+        &[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]
+    );
+    assert_eq!(
+        computer.evaluate(),
+        Ok(vec!(3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50))
+    );
+    assert!("1,x,3".parse::<IntComputer>().is_err());
+}
+
+#[test]
+fn test_peek_poke() {
+    let mut computer = IntComputer::new(vec![1, 0, 0, 0, 99]);
+    assert_eq!(computer.poke(1, 4), Ok(()));
+    assert_eq!(computer.peek(1), Ok(4));
+    assert_eq!(computer.step(), Ok(true));
+    assert_eq!(computer.peek(0), Ok(100));
+    assert_eq!(computer.step(), Ok(false));
+    assert_eq!(
+        computer.poke(5, 1),
+        Err(Fault {
+            instruction_pointer: 4,
+            kind: FaultKind::AddressOutOfBounds(5)
+        })
+    );
+}
+
+#[test]
+fn test_run_until_event() {
+    let mut computer = IntComputer::new(vec![3, 0, 4, 0, 99]);
+    assert_eq!(computer.run_until_event(), Ok(Event::NeedsInput));
+    assert_eq!(computer.run_until_event(), Ok(Event::NeedsInput));
+    computer.push_input(7);
+    assert_eq!(computer.run_until_event(), Ok(Event::Output(7)));
+    assert_eq!(computer.run_until_event(), Ok(Event::Halted));
+    assert_eq!(computer.run_until_event(), Ok(Event::Halted));
+    assert_eq!(computer.output(), &[]);
+}
+
+#[test]
+fn test_chained_machines() {
+    let amplifier: IntComputer = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0"
+        .parse()
+        .unwrap();
+    let signal = [4, 3, 2, 1, 0].iter().fold(0, |signal, &phase| {
+        let mut computer = amplifier.clone();
+        computer.push_input(phase);
+        computer.push_input(signal);
+        match computer.run_until_event() {
+            Ok(Event::Output(value)) => value,
+            event => panic!("Unexpected event {:?}", event),
+        }
+    });
+    assert_eq!(signal, 43210);
+}
+
+#[test]
+fn test_looped_machines() {
+    // Increments its input and outputs it, until it outputs 10.
+    let incrementer: IntComputer = "3,20,1001,20,1,20,4,20,1008,20,10,21,1006,21,0,99,0,0,0,0,0,0"
+        .parse()
+        .unwrap();
+    let mut machines = [incrementer.clone(), incrementer];
+    machines[0].push_input(0);
+    let mut outputs = Vec::new();
+    'running: loop {
+        for m in 0..machines.len() {
+            loop {
+                match machines[m].run_until_event().unwrap() {
+                    Event::Output(value) => {
+                        outputs.push(value);
+                        let next = (m + 1) % machines.len();
+                        machines[next].push_input(value);
+                    }
+                    Event::NeedsInput => break,
+                    Event::Halted => break 'running,
+                }
+            }
+        }
+    }
+    assert_eq!(outputs, (1..=10).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_disassemble() {
+    assert_eq!(
+        disassemble(&[1, 4, 5, 0, 99, 42, 1002, 4, 3, 4, 204, 7, 3, 21, 1]),
+        "0: ADD [4] [5] -> [0]\n\
+         4: HALT\n\
+         5: DATA 42\n\
+         6: MUL [4] 3 -> [4]\n\
+         10: OUT [rb+7]\n\
+         12: IN -> [21]\n\
+         14: DATA 1"
+    );
+}
+
+#[test]
+fn test_trace() {
+    let mut computer = IntComputer::new(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+    computer.enable_trace();
+    computer.run().unwrap();
+    assert_eq!(
+        computer.trace(),
+        &[
+            TraceEntry {
+                instruction_pointer: 0,
+                instruction: "ADD [9] [10] -> [3]".to_string(),
+                operands: vec!(30, 40),
+                write: Some((3, 70)),
+            },
+            TraceEntry {
+                instruction_pointer: 4,
+                instruction: "MUL [3] [11] -> [0]".to_string(),
+                operands: vec!(70, 50),
+                write: Some((0, 3500)),
+            },
+            TraceEntry {
+                instruction_pointer: 8,
+                instruction: "HALT".to_string(),
+                operands: vec!(),
+                write: None,
+            },
+        ]
+    );
+    assert_eq!(
+        computer.trace()[0].to_string(),
+        "0: ADD [9] [10] -> [3] ; reads 30, 40 ; [3] = 70"
+    );
+}
+
+#[test]
+fn test_trace_reads_like_untraced() {
+    // The jump isn't taken, so its out of bounds target is never read.
+    let program = vec![5, 4, 100, 99, 0];
+    let mut computer = IntComputer::new(program.clone());
+    computer.enable_trace();
+    assert_eq!(
+        computer.clone().evaluate(),
+        evaluate::<i64>(program.clone())
+    );
+    computer.run().unwrap();
+    assert_eq!(computer.trace()[0].to_string(), "0: JT [4] [100] ; reads 0");
+
+    // Faults the same way, and the faulting instruction isn't recorded.
+    let program = vec![6, 4, 100, 99, 0];
+    let mut computer = IntComputer::new(program.clone());
+    computer.enable_trace();
+    assert_eq!(computer.run(), evaluate::<i64>(program).map(|_| ()));
+    assert_eq!(computer.trace(), &[]);
+
+    // Parameters past the end of memory.
+    let mut computer = IntComputer::new(vec![1, 0, 0]);
+    computer.enable_trace();
+    assert_eq!(
+        computer.run(),
+        Err(Fault {
+            instruction_pointer: 0,
+            kind: FaultKind::AddressOutOfBounds(3)
+        })
+    );
+}
+
+#[test]
+fn test_negative_values() {
+    assert_eq!(
+        evaluate::<i64>(vec!(1101, -1, -2, 0, 99)),
+        Ok(vec!(-3, -1, -2, 0, 99))
+    );
+    let relative = vec![109, 10, 22201, -5, -4, -6, 99];
+    assert_eq!(
+        disassemble(&relative),
+        "0: ARB 10\n2: ADD [rb-5] [rb-4] -> [rb-6]\n6: HALT"
+    );
+    assert_eq!(
+        evaluate::<i64>(relative),
+        Ok(vec!(109, 10, 22201, -5, 93, -6, 99))
+    );
+    assert_eq!(
+        evaluate::<i64>(vec!(1, -1, 0, 0, 99)),
+        Err(Fault {
+            instruction_pointer: 0,
+            kind: FaultKind::InvalidAddress(-1)
+        })
+    );
+}
+
+#[test]
+fn test_overflow() {
+    let overflow = || Fault {
+        instruction_pointer: 0,
+        kind: FaultKind::Overflow,
+    };
+    assert_eq!(
+        evaluate::<u32>(vec!(1101, 4294967295, 1, 0, 99)),
+        Err(overflow())
+    );
+    assert_eq!(
+        evaluate::<i64>(vec!(1102, 9223372036854775807, 2, 0, 99)),
+        Err(overflow())
+    );
+    assert_eq!(
+        evaluate::<u64>(vec!(1102, 9223372036854775807, 2, 0, 99)),
+        Ok(vec!(18446744073709551614, 9223372036854775807, 2, 0, 99))
+    );
+}
+
+#[test]
+fn test_feedback_loop() {
+    let amplifier: IntComputer = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\
+                                  27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5"
+        .parse()
+        .unwrap();
+    let mut amplifiers = [9, 8, 7, 6, 5]
+        .iter()
+        .map(|&phase| {
+            let mut amplifier = amplifier.clone();
+            amplifier.push_input(phase);
+            amplifier
+        })
+        .collect::<Vec<_>>();
+    let mut signal = 0;
+    'running: loop {
+        for amplifier in amplifiers.iter_mut() {
+            amplifier.push_input(signal);
+            match amplifier.run_until_event().unwrap() {
+                Event::Output(value) => signal = value,
+                Event::Halted => break 'running,
+                Event::NeedsInput => panic!("Amplifier needs more input"),
+            }
+        }
+    }
+    assert_eq!(signal, 139629729);
+}
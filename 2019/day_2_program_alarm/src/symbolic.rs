@@ -0,0 +1,296 @@
+use crate::{Instruction, IntComputer, Operation, ParameterMode, Word};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// A polynomial in the noun (memory[1]) and verb (memory[2]) of a program.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Polynomial {
+    /// Coefficients keyed by the powers of the noun and verb.
+    terms: BTreeMap<(u32, u32), i128>,
+}
+
+impl Polynomial {
+    pub fn constant(value: i128) -> Self {
+        Self::term(value, 0, 0)
+    }
+
+    pub fn noun() -> Self {
+        Self::term(1, 1, 0)
+    }
+
+    pub fn verb() -> Self {
+        Self::term(1, 0, 1)
+    }
+
+    fn term(coefficient: i128, noun_power: u32, verb_power: u32) -> Self {
+        let mut polynomial = Self::default();
+        if coefficient != 0 {
+            polynomial
+                .terms
+                .insert((noun_power, verb_power), coefficient);
+        }
+        polynomial
+    }
+
+    /// The value, if the polynomial doesn't depend on the noun or verb.
+    pub fn as_constant(&self) -> Option<i128> {
+        match self.terms.iter().next() {
+            None => Some(0),
+            Some((&(0, 0), &c)) if self.terms.len() == 1 => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let mut sum = self.clone();
+        for (&powers, &c) in other.terms.iter() {
+            let coefficient = sum.terms.entry(powers).or_insert(0);
+            *coefficient = coefficient.checked_add(c)?;
+        }
+        sum.terms.retain(|_, &mut c| c != 0);
+        Some(sum)
+    }
+
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mut product = Self::default();
+        for (&(n1, v1), &c1) in self.terms.iter() {
+            for (&(n2, v2), &c2) in other.terms.iter() {
+                let coefficient = product.terms.entry((n1 + n2, v1 + v2)).or_insert(0);
+                *coefficient = coefficient.checked_add(c1.checked_mul(c2)?)?;
+            }
+        }
+        product.terms.retain(|_, &mut c| c != 0);
+        Some(product)
+    }
+
+    pub fn evaluate(&self, noun: i128, verb: i128) -> Option<i128> {
+        self.terms.iter().try_fold(0i128, |sum, (&(n, v), &c)| {
+            sum.checked_add(
+                c.checked_mul(noun.checked_pow(n)?)?
+                    .checked_mul(verb.checked_pow(v)?)?,
+            )
+        })
+    }
+
+    /// Substitutes the noun, leaving coefficients indexed by the power of
+    /// the verb.
+    fn with_noun(&self, noun: i128) -> Option<Vec<i128>> {
+        let mut coefficients = Vec::new();
+        for (&(n, v), &c) in self.terms.iter() {
+            let v = v as usize;
+            if coefficients.len() <= v {
+                coefficients.resize(v + 1, 0);
+            }
+            coefficients[v] = c
+                .checked_mul(noun.checked_pow(n)?)?
+                .checked_add(coefficients[v])?;
+        }
+        Some(coefficients)
+    }
+
+    /// The verbs within `verbs` that make the polynomial equal the target
+    /// for the given noun.
+    fn solve_for_verb(&self, noun: i128, target: i128, verbs: i128) -> Vec<i128> {
+        let coefficients = self.with_noun(noun);
+        match coefficients.as_ref().map(|c| &c[..]) {
+            Some([]) => Vec::new(),
+            Some(&[constant]) | Some(&[constant, 0]) => {
+                if constant == target {
+                    (0..=verbs).collect()
+                } else {
+                    Vec::new()
+                }
+            }
+            Some(&[constant, slope]) => target
+                .checked_sub(constant)
+                .filter(|difference| difference % slope == 0)
+                .map(|difference| difference / slope)
+                .filter(|&verb| 0 <= verb && verb <= verbs)
+                .into_iter()
+                .collect(),
+            _ => (0..=verbs)
+                .filter(|&verb| self.evaluate(noun, verb) == Some(target))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+        for (i, (&(n, v), &c)) in self.terms.iter().rev().enumerate() {
+            match (i, c < 0) {
+                (0, false) => {}
+                (0, true) => write!(f, "-")?,
+                (_, false) => write!(f, " + ")?,
+                (_, true) => write!(f, " - ")?,
+            }
+            let mut factors = Vec::new();
+            if c.abs() != 1 || (n, v) == (0, 0) {
+                factors.push(c.abs().to_string());
+            }
+            for (name, power) in [("noun", n), ("verb", v)].iter() {
+                match power {
+                    0 => {}
+                    1 => factors.push(name.to_string()),
+                    _ => factors.push(format!("{}^{}", name, power)),
+                }
+            }
+            write!(f, "{}", factors.join("*"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the program with the noun and verb as unknowns, returning the final
+/// value at address 0 in terms of them.
+///
+/// Returns `None` if the program does something that can't be expressed as
+/// a polynomial, such as using the noun or verb as an opcode or as an
+/// address to write to, jumping, or doing input or output.
+pub fn output<W: Word>(program: &IntComputer<W>) -> Option<Polynomial> {
+    // A `None` cell holds a value that isn't known, such as one read from
+    // an address that depends on the noun or verb. That's fine as long as
+    // the value is never used.
+    let mut memory = program
+        .memory
+        .iter()
+        .map(|&value| Some(Polynomial::constant(value.into())))
+        .collect::<Vec<_>>();
+    *memory.get_mut(1)? = Some(Polynomial::noun());
+    *memory.get_mut(2)? = Some(Polynomial::verb());
+
+    let mut instruction_pointer = program.instruction_pointer;
+    loop {
+        let known = |address: usize| -> Option<Option<usize>> {
+            Some(
+                memory
+                    .get(address)?
+                    .as_ref()
+                    .and_then(Polynomial::as_constant)
+                    .and_then(|value| usize::try_from(value).ok()),
+            )
+        };
+        let instruction = Instruction::decode(known(instruction_pointer)??)?;
+        let address = |parameter: usize| -> Option<Option<usize>> {
+            let address = instruction_pointer + parameter;
+            match instruction.modes[parameter - 1] {
+                ParameterMode::Position => known(address),
+                ParameterMode::Immediate => Some(Some(address)),
+                ParameterMode::Relative => None,
+            }
+        };
+        let read = |parameter: usize| -> Option<Option<&Polynomial>> {
+            match address(parameter)? {
+                Some(address) => Some(memory.get(address)?.as_ref()),
+                None => Some(None),
+            }
+        };
+        let operation: fn(&Polynomial, &Polynomial) -> Option<Polynomial> =
+            match instruction.operation {
+                Operation::Add => Polynomial::checked_add,
+                Operation::Multiply => Polynomial::checked_mul,
+                Operation::Halt => return memory.into_iter().next()?,
+                _ => return None,
+            };
+        let value = match (read(1)?, read(2)?) {
+            (Some(a), Some(b)) => Some(operation(a, b)?),
+            _ => None,
+        };
+        if instruction.modes[2] == ParameterMode::Immediate {
+            return None;
+        }
+        let write_address = address(3)??;
+        *memory.get_mut(write_address)? = value;
+        instruction_pointer += 1 + instruction.operation.arity();
+    }
+}
+
+/// Finds every noun and verb between 0 and 99 that make the program leave
+/// the target at address 0.
+///
+/// Solves the program's polynomial when it has one, and otherwise tries
+/// every pair.
+pub fn find_noun_verb<W: Word>(program: &IntComputer<W>, target: W) -> Vec<(W, W)> {
+    let candidates: Vec<(u8, u8)> = match output(program) {
+        Some(polynomial) => (0..=99)
+            .flat_map(|noun| {
+                polynomial
+                    .solve_for_verb(noun.into(), target.into(), 99)
+                    .into_iter()
+                    .map(move |verb| (noun, verb as u8))
+            })
+            .collect(),
+        None => (0..=99)
+            .flat_map(|noun| (0..=99).map(move |verb| (noun, verb)))
+            .collect(),
+    };
+    // The polynomial doesn't know about the machine's word size, so check
+    // the solutions on the real machine.
+    candidates
+        .into_iter()
+        .map(|(noun, verb)| (W::from(noun), W::from(verb)))
+        .filter(|&(noun, verb)| {
+            let mut computer = program.clone();
+            computer
+                .poke(1, noun)
+                .and_then(|_| computer.poke(2, verb))
+                .and_then(|_| computer.evaluate())
+                .map(|memory| memory[0])
+                == Ok(target)
+        })
+        .collect()
+}
+
+#[test]
+fn test_output() {
+    let output = |s: &str| output::<i64>(&s.parse().unwrap()).map(|p| p.to_string());
+    assert_eq!(output("1101,0,0,0,99"), Some("noun + verb".to_string()));
+    assert_eq!(
+        output("1101,0,0,3,1002,3,7,0,99"),
+        Some("7*noun + 7*verb".to_string())
+    );
+    assert_eq!(
+        output("1102,0,0,9,2,9,9,0,99,0"),
+        Some("noun^2*verb^2".to_string())
+    );
+    // The noun and verb are used as addresses to read from, but the value
+    // is overwritten before it's used.
+    assert_eq!(output("1,0,0,3,1101,1,2,3,99"), Some("1".to_string()));
+    // The noun and verb are used as addresses, and the value is used.
+    assert_eq!(output("1,0,0,0,99"), None);
+    // The noun and verb are used as an address to write to.
+    assert_eq!(output("1101,0,0,7,1101,1,1,0,99"), None);
+    assert_eq!(
+        output("1101,0,0,9,1002,9,-3,0,99,0"),
+        Some("-3*noun - 3*verb".to_string())
+    );
+    assert_eq!(
+        output("1101,0,0,0,1001,0,-1,0,1001,0,1,0,99"),
+        Some("noun + verb".to_string())
+    );
+    // Input depends on the outside world.
+    assert_eq!(output("3,0,0,0,99"), None);
+}
+
+#[test]
+fn test_find_noun_verb() {
+    let program: IntComputer = include_str!("../input").parse().unwrap();
+    assert_eq!(
+        output(&program).map(|p| p.to_string()),
+        Some("331776*noun + verb + 2106513".to_string())
+    );
+    assert_eq!(find_noun_verb(&program, 19690720), vec!((53, 79)));
+    assert_eq!(find_noun_verb(&program, 3), vec!());
+
+    // Control flow depends on the noun and verb, so every pair is tried.
+    let program: IntComputer = "1101,0,0,17,1006,17,12,1101,1,1,0,99,1101,5,0,0,99,0"
+        .parse()
+        .unwrap();
+    assert_eq!(output(&program), None);
+    assert_eq!(find_noun_verb(&program, 5), vec!((0, 0)));
+    assert_eq!(find_noun_verb(&program, 2).len(), 100 * 100 - 1);
+}